@@ -0,0 +1,163 @@
+// Repository configuration, read from .drake.toml
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+/// Name of the configuration file looked up in the scanned path
+pub const CONFIG_FILE: &str = ".drake.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Architectural layers, in order of precedence when a file matches several
+    #[serde(default)]
+    pub layers: Vec<Layer>,
+    /// Query files extending or replacing the built-in queries, by language name
+    #[serde(default)]
+    pub queries: BTreeMap<String, Queries>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layer {
+    pub name: String,
+    /// Globs matching paths of files in the layer, relative to the scanned path
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Names of packages in the layer
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// Layers which may be used, all layers which aren't forbidden when missing
+    pub allow: Option<Vec<String>>,
+    /// Layers which may not be used
+    #[serde(default)]
+    pub forbid: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Queries {
+    /// Query file matching declarations, relative to the scanned path
+    pub declarations: Option<String>,
+    /// Query file matching references, relative to the scanned path
+    pub references: Option<String>,
+    /// Whether the files replace the built-in queries rather than add patterns to them
+    #[serde(default)]
+    pub replace: bool,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Could not read config file {}", path.display()))?;
+
+        Self::parse(&source).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    fn parse(source: &str) -> anyhow::Result<Self> {
+        let config: Config = toml::from_str(source)?;
+
+        for (idx, layer) in config.layers.iter().enumerate() {
+            if config.layers[..idx].iter().any(|l| l.name == layer.name) {
+                bail!("Layer {} is defined more than once", layer.name);
+            }
+
+            if layer.paths.is_empty() && layer.packages.is_empty() {
+                bail!("Layer {} has no paths or packages", layer.name);
+            }
+
+            let referenced = layer.allow.iter().flatten().chain(&layer.forbid);
+
+            for name in referenced {
+                if !config.layers.iter().any(|l| &l.name == name) {
+                    bail!("Layer {} refers to an unknown layer {}", layer.name, name);
+                }
+            }
+        }
+
+        for (language, queries) in &config.queries {
+            if queries.declarations.is_none() && queries.references.is_none() {
+                bail!("Queries for {} name no query files", language);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parses_layers() {
+        let config = Config::parse(
+            r#"
+            [[layers]]
+            name = "UI"
+            paths = ["App/**"]
+            packages = ["DesignSystem"]
+            forbid = ["Data"]
+
+            [[layers]]
+            name = "Data"
+            packages = ["Storage"]
+            allow = []
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.layers.len(), 2);
+        assert_eq!(config.layers[0].paths, vec!["App/**"]);
+        assert_eq!(config.layers[0].allow, None);
+        assert_eq!(config.layers[1].allow, Some(vec![]));
+    }
+
+    #[test]
+    fn rejects_unknown_layers() {
+        let result = Config::parse(
+            r#"
+            [[layers]]
+            name = "UI"
+            paths = ["App/**"]
+            allow = ["Domain"]
+            "#,
+        );
+
+        assert_eq!(
+            format!("{:#}", result.unwrap_err()),
+            "Layer UI refers to an unknown layer Domain"
+        );
+    }
+
+    #[test]
+    fn parses_queries() {
+        let config = Config::parse(
+            r#"
+            [queries.swift]
+            references = "queries/references.scm"
+
+            [queries.kotlin]
+            declarations = "queries/kotlin.scm"
+            replace = true
+            "#,
+        )
+        .unwrap();
+
+        let swift = &config.queries["swift"];
+        let kotlin = &config.queries["kotlin"];
+
+        assert!(config.layers.is_empty());
+        assert_eq!(
+            (swift.declarations.as_deref(), swift.references.as_deref()),
+            (None, Some("queries/references.scm"))
+        );
+        assert!(!swift.replace);
+        assert_eq!(kotlin.declarations.as_deref(), Some("queries/kotlin.scm"));
+        assert!(kotlin.replace);
+    }
+}
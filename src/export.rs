@@ -0,0 +1,530 @@
+// Export of the type graph as Graphviz DOT or Mermaid
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::index::{Index, IndexItem, Kind, PackageEdge, TypeId, TypeOrigin};
+
+/// Graph description language to export to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+/// Grouping of the nodes in the exported graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Cluster {
+    /// Group types by the file they are declared in
+    File,
+    /// Group types by the package they are declared in
+    Package,
+}
+
+/// A graph of types and their dependencies, ready to be rendered
+#[derive(Debug)]
+pub struct Graph<'a> {
+    // Sorted by qualified name, and numbered in that order
+    nodes: Vec<Node<'a>>,
+    // Dependencies by node ID
+    edges: BTreeSet<(usize, usize)>,
+}
+
+#[derive(Debug)]
+struct Node<'a> {
+    id: usize,
+    name: &'a str,
+    // Kind of the primary declaration, None for external types
+    kind: Option<Kind>,
+    cluster: Option<String>,
+}
+
+impl<'a> Graph<'a> {
+    /// Build a graph of the whole index, or of the types reachable from `type_name`
+    pub fn new(
+        index: &'a Index,
+        type_name: Option<&str>,
+        include_external: bool,
+        cluster: Option<Cluster>,
+    ) -> anyhow::Result<Self> {
+        let type_ids: Vec<TypeId> = match type_name {
+            Some(type_name) => index
+                .walk(type_name)?
+                .filter_map(|(item, _)| match item {
+                    IndexItem::Type(type_id, _, _) => Some(type_id),
+                    _ => None,
+                })
+                .collect(),
+            None => index.types().map(|(type_id, _)| type_id).collect(),
+        };
+
+        // Qualified name and ID of each included type, with its node
+        let mut types = vec![];
+
+        for type_id in type_ids {
+            let t = index
+                .get_type(type_id)
+                .expect("index refers to an unknown type");
+
+            // External types nothing refers to any more are left out
+            if t.origin() == TypeOrigin::External
+                && (!include_external || index.dependents_of(type_id).is_empty())
+            {
+                continue;
+            }
+
+            // Types extending an external type only have extension declarations
+            let primary = t.primary_declaration();
+
+            let cluster = match (cluster, primary) {
+                (Some(Cluster::File), Some(declaration)) => index.file_path(declaration),
+                (Some(Cluster::Package), Some(declaration)) => {
+                    index.package_name(declaration).map(str::to_string)
+                }
+                _ => None,
+            };
+
+            let node = Node {
+                id: 0,
+                name: t.name.as_ref(),
+                kind: primary.map(|declaration| declaration.kind),
+                cluster,
+            };
+
+            types.push((t.qualified_name(), type_id, node));
+        }
+
+        // The output doesn't depend on the order the files were scanned in
+        types.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+        let node_ids: HashMap<TypeId, usize> = types
+            .iter()
+            .enumerate()
+            .map(|(id, &(_, type_id, _))| (type_id, id))
+            .collect();
+        let mut edges = BTreeSet::new();
+
+        for (id, (_, type_id, _)) in types.iter().enumerate() {
+            let t = index
+                .get_type(*type_id)
+                .expect("index refers to an unknown type");
+
+            for declaration in &t.declarations {
+                for dependency in declaration.dependencies().keys() {
+                    // Self references are not interesting in a diagram
+                    match node_ids.get(dependency) {
+                        Some(&dependency_id) if dependency_id != id => {
+                            edges.insert((id, dependency_id));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let nodes = types
+            .into_iter()
+            .enumerate()
+            .map(|(id, (_, _, node))| Node { id, ..node })
+            .collect();
+
+        Ok(Self { nodes, edges })
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.dot(),
+            GraphFormat::Mermaid => self.mermaid(),
+        }
+    }
+
+    fn dot(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("digraph drake {\n");
+        out.push_str("    rankdir=LR;\n");
+        out.push_str("    node [fontname=\"Helvetica\", shape=box, style=filled];\n");
+
+        let (clustered, unclustered) = self.clusters();
+
+        for (idx, (cluster, nodes)) in clustered.iter().enumerate() {
+            out.push_str(&format!("\n    subgraph cluster_{} {{\n", idx));
+            out.push_str(&format!("        label=\"{}\";\n", dot_escape(cluster)));
+
+            for node in nodes {
+                writeln!(out, "        {}", dot_node(node)).unwrap();
+            }
+
+            out.push_str("    }\n");
+        }
+
+        if !unclustered.is_empty() {
+            out.push('\n');
+        }
+
+        for node in unclustered {
+            writeln!(out, "    {}", dot_node(node)).unwrap();
+        }
+
+        if !self.edges.is_empty() {
+            out.push('\n');
+        }
+
+        for (from, to) in &self.edges {
+            writeln!(out, "    t{} -> t{};", from, to).unwrap();
+        }
+
+        out.push_str("}\n");
+
+        out
+    }
+
+    fn mermaid(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("graph LR\n");
+
+        let (clustered, unclustered) = self.clusters();
+
+        for (idx, (cluster, nodes)) in clustered.iter().enumerate() {
+            writeln!(
+                out,
+                "    subgraph cluster_{} [\"{}\"]",
+                idx,
+                mermaid_escape(cluster)
+            )
+            .unwrap();
+
+            for node in nodes {
+                writeln!(out, "        {}", mermaid_node(node)).unwrap();
+            }
+
+            out.push_str("    end\n");
+        }
+
+        for node in unclustered {
+            writeln!(out, "    {}", mermaid_node(node)).unwrap();
+        }
+
+        for (from, to) in &self.edges {
+            writeln!(out, "    t{} --> t{}", from, to).unwrap();
+        }
+
+        out.push_str("    classDef structType fill:#d5e8d4\n");
+        out.push_str("    classDef enumType fill:#fff2cc\n");
+        out.push_str("    classDef classType fill:#dae8fc\n");
+        out.push_str("    classDef protocolType fill:#e1d5e7\n");
+        out.push_str("    classDef extensionType fill:#f5f5f5\n");
+        out.push_str("    classDef aliasType fill:#f8cecc\n");
+        out.push_str("    classDef globalType fill:#ffe6cc\n");
+        out.push_str("    classDef externalType fill:#ffffff,stroke-dasharray:5 5\n");
+
+        out
+    }
+
+    // Nodes grouped by cluster name, and the nodes without a cluster
+    fn clusters(&self) -> (BTreeMap<&str, Vec<&Node<'a>>>, Vec<&Node<'a>>) {
+        let mut clustered: BTreeMap<&str, Vec<&Node<'a>>> = BTreeMap::new();
+        let mut unclustered = vec![];
+
+        for node in &self.nodes {
+            match &node.cluster {
+                Some(cluster) => clustered.entry(cluster.as_ref()).or_default().push(node),
+                None => unclustered.push(node),
+            }
+        }
+
+        (clustered, unclustered)
+    }
+}
+
+/// Render the package graph as Graphviz DOT, edges are labeled with the number of references
+pub fn package_dot(index: &Index, edges: &[PackageEdge]) -> String {
+    let mut out = String::new();
+
+    out.push_str("digraph drake {\n");
+    out.push_str("    rankdir=LR;\n");
+    out.push_str(
+        "    node [fontname=\"Helvetica\", shape=folder, style=filled, fillcolor=\"#dae8fc\"];\n",
+    );
+
+    if index.packages().next().is_some() {
+        out.push('\n');
+    }
+
+    for (package_id, package) in index.packages() {
+        writeln!(
+            out,
+            "    p{} [label=\"{}\"];",
+            package_id,
+            dot_escape(package.name())
+        )
+        .unwrap();
+    }
+
+    if !edges.is_empty() {
+        out.push('\n');
+    }
+
+    for edge in edges {
+        writeln!(
+            out,
+            "    p{} -> p{} [label=\"{}\"];",
+            edge.from, edge.to, edge.count
+        )
+        .unwrap();
+    }
+
+    out.push_str("}\n");
+
+    out
+}
+
+fn dot_node(node: &Node) -> String {
+    let style = match node.kind {
+        Some(Kind::Struct) => "style=\"rounded,filled\", fillcolor=\"#d5e8d4\"",
+        Some(Kind::Enum) => "shape=hexagon, fillcolor=\"#fff2cc\"",
+        Some(Kind::Class) => "fillcolor=\"#dae8fc\"",
+        Some(Kind::Actor) => "peripheries=2, fillcolor=\"#dae8fc\"",
+        Some(Kind::Protocol) => "shape=ellipse, fillcolor=\"#e1d5e7\"",
+        Some(Kind::Extension) => "fillcolor=\"#f5f5f5\"",
+        Some(Kind::TypeAlias | Kind::AssociatedType) => "shape=cds, fillcolor=\"#f8cecc\"",
+        Some(Kind::Function | Kind::Macro) => "shape=parallelogram, fillcolor=\"#ffe6cc\"",
+        Some(Kind::Variable | Kind::Constant) => "shape=note, fillcolor=\"#ffe6cc\"",
+        None => "style=dashed",
+    };
+
+    format!(
+        "t{} [label=\"{}\", {}];",
+        node.id,
+        dot_escape(node.name),
+        style
+    )
+}
+
+fn mermaid_node(node: &Node) -> String {
+    let name = mermaid_escape(node.name);
+
+    match node.kind {
+        Some(Kind::Struct) => format!("t{}(\"{}\"):::structType", node.id, name),
+        Some(Kind::Enum) => format!("t{}{{{{\"{}\"}}}}:::enumType", node.id, name),
+        Some(Kind::Class) => format!("t{}[\"{}\"]:::classType", node.id, name),
+        Some(Kind::Actor) => format!("t{}[[\"{}\"]]:::classType", node.id, name),
+        Some(Kind::Protocol) => format!("t{}([\"{}\"]):::protocolType", node.id, name),
+        Some(Kind::Extension) => format!("t{}[/\"{}\"/]:::extensionType", node.id, name),
+        Some(Kind::TypeAlias | Kind::AssociatedType) => {
+            format!("t{}>\"{}\"]:::aliasType", node.id, name)
+        }
+        Some(Kind::Function | Kind::Macro) => {
+            format!("t{}[/\"{}\"\\]:::globalType", node.id, name)
+        }
+        Some(Kind::Variable | Kind::Constant) => {
+            format!("t{}[(\"{}\")]:::globalType", node.id, name)
+        }
+        None => format!("t{}[\"{}\"]:::externalType", node.id, name),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Point;
+
+    use super::*;
+    use crate::index::{Confidence, ReferenceKind};
+
+    fn index() -> Index {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[
+                (
+                    "OtherType",
+                    &Point::new(11, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "ExternalType",
+                    &Point::new(12, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OtherType",
+                    &Point::new(13, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Protocol,
+            "./OtherType.swift",
+            Point::new(1, 9),
+            &[(
+                "OtherType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        index
+    }
+
+    #[test]
+    fn renders_dot_clustered_by_file() {
+        let index = index();
+        let graph = Graph::new(&index, None, true, Some(Cluster::File)).unwrap();
+
+        let expected = r##"digraph drake {
+    rankdir=LR;
+    node [fontname="Helvetica", shape=box, style=filled];
+
+    subgraph cluster_0 {
+        label="./MyType.swift";
+        t1 [label="MyType", style="rounded,filled", fillcolor="#d5e8d4"];
+    }
+
+    subgraph cluster_1 {
+        label="./OtherType.swift";
+        t2 [label="OtherType", shape=ellipse, fillcolor="#e1d5e7"];
+    }
+
+    t0 [label="ExternalType", style=dashed];
+
+    t1 -> t0;
+    t1 -> t2;
+}
+"##;
+
+        assert_eq!(graph.render(GraphFormat::Dot), expected);
+    }
+
+    #[test]
+    fn renders_mermaid_reachable_from_a_type() {
+        let index = index();
+        let graph = Graph::new(&index, Some("MyType"), false, None).unwrap();
+
+        let expected = r##"graph LR
+    t0("MyType"):::structType
+    t1(["OtherType"]):::protocolType
+    t0 --> t1
+    classDef structType fill:#d5e8d4
+    classDef enumType fill:#fff2cc
+    classDef classType fill:#dae8fc
+    classDef protocolType fill:#e1d5e7
+    classDef extensionType fill:#f5f5f5
+    classDef aliasType fill:#f8cecc
+    classDef globalType fill:#ffe6cc
+    classDef externalType fill:#ffffff,stroke-dasharray:5 5
+"##;
+
+        assert_eq!(graph.render(GraphFormat::Mermaid), expected);
+    }
+
+    #[test]
+    fn numbers_nodes_regardless_of_the_scan_order() {
+        let index = index();
+        let mut reversed = Index::new();
+        reversed.add_declaration(
+            "OtherType",
+            Kind::Protocol,
+            "./OtherType.swift",
+            Point::new(1, 9),
+            &[],
+        );
+        reversed.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[
+                (
+                    "ExternalType",
+                    &Point::new(12, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OtherType",
+                    &Point::new(11, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+
+        for format in [GraphFormat::Dot, GraphFormat::Mermaid] {
+            let graph = Graph::new(&index, None, true, None).unwrap();
+            let reversed_graph = Graph::new(&reversed, None, true, None).unwrap();
+
+            assert_eq!(reversed_graph.render(format), graph.render(format));
+        }
+    }
+
+    #[test]
+    fn renders_package_dot() {
+        let mut index = Index::new();
+        index.add_package("App", "./App");
+        index.add_package("Core", "./Core");
+        index.add_declaration(
+            "AppView",
+            Kind::Struct,
+            "./App/AppView.swift",
+            Point::new(1, 7),
+            &[
+                (
+                    "Model",
+                    &Point::new(2, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "Model",
+                    &Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "Model",
+            Kind::Struct,
+            "./Core/Model.swift",
+            Point::new(1, 7),
+            &[],
+        );
+
+        let expected = r##"digraph drake {
+    rankdir=LR;
+    node [fontname="Helvetica", shape=folder, style=filled, fillcolor="#dae8fc"];
+
+    p0 [label="App"];
+    p1 [label="Core"];
+
+    p0 -> p1 [label="2"];
+}
+"##;
+
+        assert_eq!(package_dot(&index, &index.package_graph()), expected);
+    }
+}
@@ -1,8 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 use tree_sitter::Point;
 
-use super::{Declaration, Index, Type, TypeId, TypeOrigin};
+use super::{Declaration, Index, PackageId, ReferenceKind, Type, TypeId, TypeOrigin};
 
 #[derive(Debug, PartialEq)]
 pub enum IndexItem<'a> {
@@ -22,6 +22,10 @@ pub struct IndexCursor<'a> {
     path: Vec<Segment>,
     /// Set of types we have seen already, to prevent revisiting types
     visited_types: HashSet<TypeId>,
+    /// Only follow declarations made in this package
+    package: Option<PackageId>,
+    /// Only follow references of these kinds
+    kinds: Option<Vec<ReferenceKind>>,
 }
 
 enum Segment {
@@ -47,17 +51,27 @@ impl<'a> IndexCursor<'a> {
             index,
             path: vec![Segment::Type(type_id)],
             visited_types: HashSet::new(),
+            package: None,
+            kinds: None,
         }
     }
 
+    /// Skip declarations made outside of a package
+    pub fn in_package(mut self, package: PackageId) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Skip references of other kinds
+    pub fn of_kinds(mut self, kinds: &[ReferenceKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+
     pub fn next_item(&mut self) -> Option<(IndexItem<'a>, usize)> {
         loop {
-            let Some(top) = self.path.last() else {
-                return None;
-            };
-            let Some(current_type) = self.current_type() else {
-                return None;
-            };
+            let top = self.path.last()?;
+            let current_type = self.current_type()?;
             let parent = self.parent_item();
             let depth = self.path.len() - 1;
 
@@ -98,6 +112,15 @@ impl<'a> IndexCursor<'a> {
                         continue;
                     };
 
+                    if self.package.is_some() && self.index.package_id(declaration) != self.package
+                    {
+                        let next_declaration_index = idx + 1;
+
+                        self.path.pop();
+                        self.path.push(Segment::Declaration(next_declaration_index));
+                        continue;
+                    }
+
                     if !declaration.dependencies.is_empty() {
                         self.path.push(Segment::Dependency(0));
                     } else if current_type.declarations.len() > *idx + 1 {
@@ -120,7 +143,7 @@ impl<'a> IndexCursor<'a> {
                         unreachable!("Cannot find a declaration while visiting a dependency");
                     };
 
-                    let Some((type_id, point)) = declaration.dependencies.get(*idx) else {
+                    let Some((type_id, point, kind, _)) = declaration.dependencies.get(*idx) else {
                         // Dependency index has run over, backtrack
                         let next_declaration_index = dec_idx + 1;
                         self.path.pop();
@@ -130,6 +153,16 @@ impl<'a> IndexCursor<'a> {
                         continue;
                     };
 
+                    if let Some(kinds) = &self.kinds {
+                        if !kinds.contains(&self.index.classify(*kind, *type_id)) {
+                            let next_dependency_index = idx + 1;
+
+                            self.path.pop();
+                            self.path.push(Segment::Dependency(next_dependency_index));
+                            continue;
+                        }
+                    }
+
                     let Some(type_ref) = self.index.get_type(*type_id) else {
                         unreachable!("Cannot find type {} while visiting a dependency", type_id);
                     };
@@ -184,12 +217,12 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
-    use crate::index::{Index, Kind};
+    use crate::index::{Confidence, Index, Kind, ReferenceKind};
 
     #[test]
     fn emits_a_single_reference() {
         let mut index = Index::new();
-        index.add_reference("MyType");
+        index.add_type(None, "MyType");
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
         let expected = vec![(IndexItem::Type(0, "MyType", TypeOrigin::External), 0)];
@@ -213,6 +246,7 @@ mod tests {
             point: Point::new(10, 20),
             file: 0,
             dependencies: vec![],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -239,6 +273,7 @@ mod tests {
             point: Point::new(10, 20),
             file: 0,
             dependencies: vec![],
+            unresolved: vec![],
         };
 
         index.add_declaration(
@@ -253,6 +288,7 @@ mod tests {
             point: Point::new(5, 10),
             file: 1,
             dependencies: vec![],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -273,14 +309,25 @@ mod tests {
             Kind::Enum,
             "./MyType.swift",
             Point::new(10, 20),
-            &[("OtherType", &Point::new(3, 10))],
+            &[(
+                "OtherType",
+                &Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
         );
 
         let declaration = Declaration {
             kind: Kind::Enum,
             point: Point::new(10, 20),
             file: 0,
-            dependencies: vec![(0, Point::new(3, 10))],
+            dependencies: vec![(
+                0,
+                Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -302,7 +349,12 @@ mod tests {
             Kind::Enum,
             "./MyType.swift",
             Point::new(10, 20),
-            &[("OtherType", &Point::new(3, 10))],
+            &[(
+                "OtherType",
+                &Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
         );
         index.add_declaration(
             "OtherType",
@@ -317,13 +369,20 @@ mod tests {
             kind: Kind::Enum,
             point: Point::new(10, 20),
             file: 0,
-            dependencies: vec![(other_type_id, Point::new(3, 10))],
+            dependencies: vec![(
+                other_type_id,
+                Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+            unresolved: vec![],
         };
         let declaration_2 = Declaration {
             kind: Kind::Struct,
             point: Point::new(10, 20),
             file: 1,
             dependencies: vec![],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -347,8 +406,18 @@ mod tests {
             "./MyType.swift",
             Point::new(10, 20),
             &[
-                ("OtherType", &Point::new(3, 10)),
-                ("YetAnotherType", &Point::new(7, 10)),
+                (
+                    "OtherType",
+                    &Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "YetAnotherType",
+                    &Point::new(7, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
         );
 
@@ -359,9 +428,20 @@ mod tests {
             point: Point::new(10, 20),
             file: 0,
             dependencies: vec![
-                (other_type_id, Point::new(3, 10)),
-                (yet_another_type_id, Point::new(7, 10)),
+                (
+                    other_type_id,
+                    Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    yet_another_type_id,
+                    Point::new(7, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -392,8 +472,18 @@ mod tests {
             "./MyType.swift",
             Point::new(10, 20),
             &[
-                ("ExternalType", &Point::new(3, 10)),
-                ("OtherType", &Point::new(8, 10)),
+                (
+                    "ExternalType",
+                    &Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OtherType",
+                    &Point::new(8, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
         );
         index.add_declaration(
@@ -402,9 +492,24 @@ mod tests {
             "./OtherType.swift",
             Point::new(10, 20),
             &[
-                ("ExternalType", &Point::new(4, 10)),
-                ("OneMoreType", &Point::new(5, 10)),
-                ("AnotherExternalType", &Point::new(6, 10)),
+                (
+                    "ExternalType",
+                    &Point::new(4, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OneMoreType",
+                    &Point::new(5, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "AnotherExternalType",
+                    &Point::new(6, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
         );
         index.add_declaration(
@@ -412,7 +517,12 @@ mod tests {
             Kind::Struct,
             "./OneMoreType.swift",
             Point::new(10, 20),
-            &[("AnotherExternalType", &Point::new(6, 10))],
+            &[(
+                "AnotherExternalType",
+                &Point::new(6, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
         );
 
         let declaration_1 = Declaration {
@@ -420,22 +530,46 @@ mod tests {
             point: Point::new(10, 20),
             file: 0,
             dependencies: vec![
-                (index.type_id("ExternalType").unwrap(), Point::new(3, 10)),
-                (index.type_id("OtherType").unwrap(), Point::new(8, 10)),
+                (
+                    index.type_id("ExternalType").unwrap(),
+                    Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    index.type_id("OtherType").unwrap(),
+                    Point::new(8, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
+            unresolved: vec![],
         };
         let declaration_2 = Declaration {
             kind: Kind::Struct,
             point: Point::new(10, 20),
             file: 1,
             dependencies: vec![
-                (index.type_id("ExternalType").unwrap(), Point::new(4, 10)),
-                (index.type_id("OneMoreType").unwrap(), Point::new(5, 10)),
+                (
+                    index.type_id("ExternalType").unwrap(),
+                    Point::new(4, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    index.type_id("OneMoreType").unwrap(),
+                    Point::new(5, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
                 (
                     index.type_id("AnotherExternalType").unwrap(),
                     Point::new(6, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
                 ),
             ],
+            unresolved: vec![],
         };
         let declaration_3 = Declaration {
             kind: Kind::Struct,
@@ -444,7 +578,10 @@ mod tests {
             dependencies: vec![(
                 index.type_id("AnotherExternalType").unwrap(),
                 Point::new(6, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
             )],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -494,7 +631,12 @@ mod tests {
             Kind::Enum,
             "./MyType.swift",
             Point::new(10, 20),
-            &[("OtherType", &Point::new(8, 10))],
+            &[(
+                "OtherType",
+                &Point::new(8, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
         );
         index.add_declaration(
             "MyType",
@@ -502,9 +644,24 @@ mod tests {
             "./Extension.swift",
             Point::new(12, 20),
             &[
-                ("ExternalType", &Point::new(4, 10)),
-                ("OneMoreType", &Point::new(5, 10)),
-                ("AnotherExternalType", &Point::new(6, 10)),
+                (
+                    "ExternalType",
+                    &Point::new(4, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OneMoreType",
+                    &Point::new(5, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "AnotherExternalType",
+                    &Point::new(6, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
         );
         index.add_declaration(
@@ -512,33 +669,63 @@ mod tests {
             Kind::Struct,
             "./OneMoreType.swift",
             Point::new(10, 20),
-            &[("ExternalType", &Point::new(6, 10))],
+            &[(
+                "ExternalType",
+                &Point::new(6, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
         );
 
         let declaration_1 = Declaration {
             kind: Kind::Enum,
             point: Point::new(10, 20),
             file: 0,
-            dependencies: vec![(index.type_id("OtherType").unwrap(), Point::new(8, 10))],
+            dependencies: vec![(
+                index.type_id("OtherType").unwrap(),
+                Point::new(8, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+            unresolved: vec![],
         };
         let declaration_2 = Declaration {
             kind: Kind::Extension,
             point: Point::new(12, 20),
             file: 1,
             dependencies: vec![
-                (index.type_id("ExternalType").unwrap(), Point::new(4, 10)),
-                (index.type_id("OneMoreType").unwrap(), Point::new(5, 10)),
+                (
+                    index.type_id("ExternalType").unwrap(),
+                    Point::new(4, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    index.type_id("OneMoreType").unwrap(),
+                    Point::new(5, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
                 (
                     index.type_id("AnotherExternalType").unwrap(),
                     Point::new(6, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
                 ),
             ],
+            unresolved: vec![],
         };
         let declaration_3 = Declaration {
             kind: Kind::Struct,
             point: Point::new(10, 20),
             file: 2,
-            dependencies: vec![(index.type_id("ExternalType").unwrap(), Point::new(6, 10))],
+            dependencies: vec![(
+                index.type_id("ExternalType").unwrap(),
+                Point::new(6, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -585,8 +772,18 @@ mod tests {
             "./MyType.swift",
             Point::new(10, 20),
             &[
-                ("ExternalType", &Point::new(7, 10)),
-                ("OtherType", &Point::new(3, 10)),
+                (
+                    "ExternalType",
+                    &Point::new(7, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OtherType",
+                    &Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
         );
         index.add_declaration(
@@ -595,8 +792,18 @@ mod tests {
             "./OtherType.swift",
             Point::new(10, 20),
             &[
-                ("MyType", &Point::new(7, 10)),
-                ("ExternalType", &Point::new(3, 10)),
+                (
+                    "MyType",
+                    &Point::new(7, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "ExternalType",
+                    &Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
         );
 
@@ -605,9 +812,20 @@ mod tests {
             point: Point::new(10, 20),
             file: 0,
             dependencies: vec![
-                (index.type_id("ExternalType").unwrap(), Point::new(7, 10)),
-                (index.type_id("OtherType").unwrap(), Point::new(3, 10)),
+                (
+                    index.type_id("ExternalType").unwrap(),
+                    Point::new(7, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    index.type_id("OtherType").unwrap(),
+                    Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
+            unresolved: vec![],
         };
 
         let declaration_2 = Declaration {
@@ -615,9 +833,20 @@ mod tests {
             point: Point::new(10, 20),
             file: 1,
             dependencies: vec![
-                (index.type_id("MyType").unwrap(), Point::new(7, 10)),
-                (index.type_id("ExternalType").unwrap(), Point::new(3, 10)),
+                (
+                    index.type_id("MyType").unwrap(),
+                    Point::new(7, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    index.type_id("ExternalType").unwrap(),
+                    Point::new(3, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
             ],
+            unresolved: vec![],
         };
 
         let actual: Vec<_> = index.walk("MyType").unwrap().collect();
@@ -0,0 +1,202 @@
+use super::{Index, TypeId};
+
+/// Find strongly connected components of the type graph using Tarjan's algorithm
+/// Components are returned in reverse topological order, every type is in exactly one
+/// component. The search is iterative, so that deep graphs don't overflow the stack.
+pub fn strongly_connected_components(index: &Index) -> Vec<Vec<TypeId>> {
+    let count = index.types.len();
+
+    let mut search = Search {
+        index,
+        next_order: 0,
+        order: vec![None; count],
+        lowlink: vec![0; count],
+        on_stack: vec![false; count],
+        stack: vec![],
+        call_stack: vec![],
+        components: vec![],
+    };
+
+    for root in 0..count {
+        if search.order[root].is_none() {
+            search.run(root);
+        }
+    }
+
+    search.components
+}
+
+struct Search<'a> {
+    index: &'a Index,
+    next_order: usize,
+    /// Order in which types were first visited
+    order: Vec<Option<usize>>,
+    /// Lowest order reachable from each type
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    /// Visited types not yet assigned to a component
+    stack: Vec<TypeId>,
+    /// Types being visited, their successors and the index of the next successor to visit
+    call_stack: Vec<(TypeId, Vec<TypeId>, usize)>,
+    components: Vec<Vec<TypeId>>,
+}
+
+impl Search<'_> {
+    fn visit(&mut self, type_id: TypeId) {
+        self.order[type_id] = Some(self.next_order);
+        self.lowlink[type_id] = self.next_order;
+        self.next_order += 1;
+
+        self.stack.push(type_id);
+        self.on_stack[type_id] = true;
+
+        self.call_stack
+            .push((type_id, self.index.successors(type_id), 0));
+    }
+
+    fn run(&mut self, root: TypeId) {
+        self.visit(root);
+
+        while let Some((type_id, successors, next)) = self.call_stack.last_mut() {
+            let type_id = *type_id;
+
+            if let Some(&successor) = successors.get(*next) {
+                *next += 1;
+
+                match self.order[successor] {
+                    None => self.visit(successor),
+                    Some(successor_order) if self.on_stack[successor] => {
+                        self.lowlink[type_id] = self.lowlink[type_id].min(successor_order);
+                    }
+                    Some(_) => (),
+                }
+
+                continue;
+            }
+
+            // All successors visited, backtrack
+            self.call_stack.pop();
+
+            if let Some(&(parent, _, _)) = self.call_stack.last() {
+                self.lowlink[parent] = self.lowlink[parent].min(self.lowlink[type_id]);
+            }
+
+            if Some(self.lowlink[type_id]) == self.order[type_id] {
+                let mut component = vec![];
+
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+
+                    if member == type_id {
+                        break;
+                    }
+                }
+
+                self.components.push(component);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Point;
+
+    use crate::index::{Confidence, Index, Kind, ReferenceKind};
+
+    fn add(index: &mut Index, name: &str, references: &[&str]) {
+        let point = Point::new(1, 1);
+        let references: Vec<_> = references
+            .iter()
+            .map(|r| (*r, &point, ReferenceKind::BodyCall, Confidence::High))
+            .collect();
+
+        index.add_declaration(name, Kind::Class, "./File.swift", point, &references);
+    }
+
+    fn names(index: &Index, cycles: Vec<Vec<usize>>) -> Vec<Vec<&str>> {
+        cycles
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .into_iter()
+                    .map(|id| index.get_type(id).unwrap().name.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_no_cycles_in_a_tree() {
+        let mut index = Index::new();
+        add(&mut index, "A", &["B", "C", "A"]);
+        add(&mut index, "B", &["C", "External"]);
+
+        assert!(index.cycles().is_empty());
+    }
+
+    #[test]
+    fn finds_a_direct_cycle() {
+        let mut index = Index::new();
+        add(&mut index, "A", &["B", "External"]);
+        add(&mut index, "B", &["A"]);
+
+        let actual = names(&index, index.cycles());
+
+        assert_eq!(actual, vec![vec!["A", "B"]]);
+    }
+
+    #[test]
+    fn finds_separate_transitive_cycles() {
+        let mut index = Index::new();
+        add(&mut index, "A", &["B"]);
+        add(&mut index, "B", &["C", "D"]);
+        add(&mut index, "C", &["A"]);
+        add(&mut index, "D", &["E"]);
+        add(&mut index, "E", &["F"]);
+        add(&mut index, "F", &["D", "External"]);
+
+        let actual = names(&index, index.cycles());
+
+        assert_eq!(actual, vec![vec!["A", "B", "C"], vec!["D", "E", "F"]]);
+    }
+
+    #[test]
+    fn finds_packages_declaring_a_cycle() {
+        let mut index = Index::new();
+        let core = index.add_package("Core", "./Packages/Core");
+        let ui = index.add_package("UI", "./Packages/UI");
+
+        let point = Point::new(1, 1);
+        let mut declare = |name, path, reference| {
+            index.add_declaration(
+                name,
+                Kind::Class,
+                path,
+                point,
+                &[(reference, &point, ReferenceKind::BodyCall, Confidence::High)],
+            );
+        };
+
+        declare("Model", "./Packages/Core/Model.swift", "View");
+        declare("View", "./Packages/UI/View.swift", "Model");
+        declare("Store", "./Packages/Core/Store.swift", "App");
+        declare("App", "./App/App.swift", "Store");
+
+        let packages: Vec<_> = index
+            .cycles()
+            .iter()
+            .map(|cycle| index.declaring_packages(cycle))
+            .collect();
+
+        // App is declared outside of any package, so its cycle stays within Core
+        assert_eq!(
+            packages,
+            vec![BTreeSet::from([core]), BTreeSet::from([core, ui])]
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashSet;
+
+use super::{Declaration, Index, PackageId, ReferenceKind, TypeId};
+
+/// A declaration which references a type reached by the walk
+#[derive(Debug, PartialEq)]
+pub struct Dependent<'a> {
+    /// Type the declaration belongs to
+    pub type_id: TypeId,
+    pub name: &'a str,
+    pub declaration: &'a Declaration,
+    /// Type the declaration references
+    pub dependency: TypeId,
+}
+
+/// A stateful object representing a search through the reverse edges of the index graph
+/// Each dependent type is only followed further the first time it is reached, which
+/// also prevents following back edges
+pub struct DependentsCursor<'a> {
+    index: &'a Index,
+    /// Path in the graph from the entry point
+    /// Each item is a type and the index of the next reverse edge to follow from it
+    path: Vec<(TypeId, usize)>,
+    /// Set of types we have seen already, to prevent revisiting types
+    visited_types: HashSet<TypeId>,
+    /// Only follow declarations made in this package
+    package: Option<PackageId>,
+    /// Only follow references of these kinds
+    kinds: Option<Vec<ReferenceKind>>,
+}
+
+// Used to produce output like this
+//
+// Types depending on BaseViewControler:
+// - LoginViewController, class declared in ./.../LoginViewController.swift 11:13, using BaseViewControler at 11:34
+//   - AppDelegate, class declared in ./.../AppDelegate.swift 12:6, using LoginViewController at 19:17
+
+impl<'a> DependentsCursor<'a> {
+    pub fn new(index: &'a Index, type_id: TypeId) -> Self {
+        Self {
+            index,
+            path: vec![(type_id, 0)],
+            visited_types: HashSet::from([type_id]),
+            package: None,
+            kinds: None,
+        }
+    }
+
+    /// Skip declarations made outside of a package
+    pub fn in_package(mut self, package: PackageId) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Skip declarations only making references of other kinds
+    pub fn of_kinds(mut self, kinds: &[ReferenceKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+
+    pub fn next_item(&mut self) -> Option<(Dependent<'a>, usize)> {
+        loop {
+            let depth = self.path.len().checked_sub(1)?;
+            let (dependency, edge_idx) = self.path.last_mut()?;
+            let dependency = *dependency;
+
+            let Some(&(type_id, declaration_idx)) =
+                self.index.dependents_of(dependency).get(*edge_idx)
+            else {
+                // Reverse edges have run out, backtrack
+                self.path.pop();
+                continue;
+            };
+
+            *edge_idx += 1;
+
+            // A type referring to itself, e.g. in a property of a recursive type, isn't
+            // a dependent of its own
+            if type_id == dependency {
+                continue;
+            }
+
+            let Some(dependent_type) = self.index.get_type(type_id) else {
+                unreachable!("Cannot find type {} while visiting a dependent", type_id);
+            };
+            let Some(declaration) = dependent_type.declarations.get(declaration_idx) else {
+                unreachable!("Cannot find a declaration while visiting a dependent");
+            };
+
+            if self.package.is_some() && self.index.package_id(declaration) != self.package {
+                continue;
+            }
+
+            if let Some(kinds) = &self.kinds {
+                let reference_kinds = self.index.reference_kinds(declaration, dependency);
+
+                if !kinds.iter().any(|kind| reference_kinds.contains(kind)) {
+                    continue;
+                }
+            }
+
+            if self.visited_types.insert(type_id) {
+                // Visit the dependents of the dependent type
+                self.path.push((type_id, 0));
+            }
+
+            return Some((
+                Dependent {
+                    type_id,
+                    name: dependent_type.name.as_ref(),
+                    declaration,
+                    dependency,
+                },
+                depth,
+            ));
+        }
+    }
+}
+
+impl<'a> Iterator for DependentsCursor<'a> {
+    // A dependent declaration and the length of the current path from the starting type
+    type Item = (Dependent<'a>, usize);
+
+    fn next(&mut self) -> Option<(Dependent<'a>, usize)> {
+        self.next_item()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Point;
+
+    use crate::index::{Confidence, Index, Kind, ReferenceKind};
+
+    #[test]
+    fn emits_nothing_for_an_unused_type() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[(
+                "OtherType",
+                &Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        let actual: Vec<_> = index.walk_dependents("MyType").unwrap().collect();
+
+        assert!(actual.is_empty())
+    }
+
+    #[test]
+    fn skips_types_referring_to_themselves() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "Node",
+            Kind::Class,
+            "./Node.swift",
+            Point::new(0, 6),
+            &[(
+                "Node",
+                &Point::new(1, 14),
+                ReferenceKind::StoredProperty,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "Tree",
+            Kind::Struct,
+            "./Tree.swift",
+            Point::new(0, 7),
+            &[(
+                "Node",
+                &Point::new(1, 14),
+                ReferenceKind::StoredProperty,
+                Confidence::High,
+            )],
+        );
+
+        let actual: Vec<_> = index
+            .walk_dependents("Node")
+            .unwrap()
+            .map(|(dependent, depth)| (dependent.name, depth))
+            .collect();
+
+        assert_eq!(actual, vec![("Tree", 0)]);
+    }
+
+    #[test]
+    fn emits_transitive_dependents() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[(
+                "OtherType",
+                &Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Class,
+            "./OtherType.swift",
+            Point::new(1, 6),
+            &[
+                (
+                    "BaseType",
+                    &Point::new(1, 18),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "BaseType",
+                    &Point::new(4, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "YetAnotherType",
+            Kind::Enum,
+            "./YetAnotherType.swift",
+            Point::new(2, 5),
+            &[(
+                "BaseType",
+                &Point::new(5, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        let my_type = index.type_id("MyType").unwrap();
+        let other_type = index.type_id("OtherType").unwrap();
+        let yet_another_type = index.type_id("YetAnotherType").unwrap();
+        let base_type = index.type_id("BaseType").unwrap();
+
+        let actual: Vec<_> = index
+            .walk_dependents("BaseType")
+            .unwrap()
+            .map(|(dependent, depth)| (dependent.name, dependent.dependency, depth))
+            .collect();
+        let expected = vec![
+            ("OtherType", base_type, 0),
+            ("MyType", other_type, 1),
+            ("YetAnotherType", base_type, 0),
+        ];
+
+        assert_eq!(actual, expected);
+
+        let declarations: Vec<_> = index
+            .walk_dependents("BaseType")
+            .unwrap()
+            .map(|(dependent, _)| dependent.declaration.point)
+            .collect();
+
+        assert_eq!(
+            declarations,
+            vec![Point::new(1, 6), Point::new(10, 20), Point::new(2, 5)]
+        );
+        assert!(index.dependents_of(my_type).is_empty());
+        assert!(index.dependents_of(yet_another_type).is_empty());
+    }
+
+    #[test]
+    fn emits_every_declaration_but_follows_each_type_once() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[(
+                "OtherType",
+                &Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "MyType",
+            Kind::Extension,
+            "./MyType+Other.swift",
+            Point::new(1, 10),
+            &[(
+                "OtherType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Struct,
+            "./OtherType.swift",
+            Point::new(1, 7),
+            &[(
+                "MyType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        let actual: Vec<_> = index
+            .walk_dependents("OtherType")
+            .unwrap()
+            .map(|(dependent, depth)| (dependent.name, dependent.declaration.kind, depth))
+            .collect();
+        let expected = vec![
+            ("MyType", Kind::Struct, 0),
+            ("OtherType", Kind::Struct, 1),
+            ("MyType", Kind::Extension, 0),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn skips_declarations_without_references_of_the_given_kinds() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[(
+                "OtherType",
+                &Point::new(10, 30),
+                ReferenceKind::Conformance,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "YetAnotherType",
+            Kind::Struct,
+            "./YetAnotherType.swift",
+            Point::new(1, 7),
+            &[(
+                "OtherType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Protocol,
+            "./OtherType.swift",
+            Point::new(1, 9),
+            &[],
+        );
+
+        let actual: Vec<_> = index
+            .walk_dependents("OtherType")
+            .unwrap()
+            .of_kinds(&[ReferenceKind::Conformance])
+            .map(|(dependent, depth)| (dependent.name, depth))
+            .collect();
+
+        assert_eq!(actual, vec![("MyType", 0)]);
+    }
+
+    #[test]
+    fn skips_declarations_outside_of_a_package() {
+        let mut index = Index::new();
+        index.add_package("Feature", "./Feature");
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./Feature/MyType.swift",
+            Point::new(10, 20),
+            &[(
+                "OtherType",
+                &Point::new(3, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "MyType",
+            Kind::Extension,
+            "./App/MyType+Other.swift",
+            Point::new(1, 10),
+            &[(
+                "OtherType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        let feature = index.package_id_by_name("Feature").unwrap();
+        let actual: Vec<_> = index
+            .walk_dependents("OtherType")
+            .unwrap()
+            .in_package(feature)
+            .map(|(dependent, depth)| (dependent.name, dependent.declaration.kind, depth))
+            .collect();
+
+        assert_eq!(actual, vec![("MyType", Kind::Struct, 0)]);
+    }
+}
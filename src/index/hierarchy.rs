@@ -0,0 +1,351 @@
+use std::collections::HashSet;
+
+use super::{Index, Kind, ReferenceKind, TypeId};
+
+/// A type reached by walking the inheritance and conformance relationships
+#[derive(Debug, PartialEq)]
+pub struct HierarchyEntry {
+    pub type_id: TypeId,
+    /// Declaration stating the relationship, as (type ID, index into the type's declarations).
+    /// Walking up it's a declaration of the subtype, walking down of the reached type.
+    pub declaration: (TypeId, usize),
+    /// Either `Inheritance` or `Conformance`
+    pub kind: ReferenceKind,
+    pub depth: usize,
+}
+
+/// Superclasses and adopted protocols of a type, depth first. Each type is only listed
+/// once, superclasses before protocols.
+pub fn supertypes(index: &Index, type_id: TypeId) -> Vec<HierarchyEntry> {
+    let mut entries = vec![];
+    let mut visited = HashSet::from([type_id]);
+
+    walk_up(index, type_id, 0, &mut visited, &mut entries);
+
+    entries
+}
+
+/// Subclasses of a type and types conforming to it, including conformances added in
+/// extensions, depth first. Each type is only listed once, sorted by kind and name.
+pub fn subtypes(index: &Index, type_id: TypeId) -> Vec<HierarchyEntry> {
+    let mut entries = vec![];
+    let mut visited = HashSet::from([type_id]);
+
+    walk_down(index, type_id, 0, &mut visited, &mut entries);
+
+    entries
+}
+
+fn walk_up(
+    index: &Index,
+    type_id: TypeId,
+    depth: usize,
+    visited: &mut HashSet<TypeId>,
+    entries: &mut Vec<HierarchyEntry>,
+) {
+    let mut parents: Vec<HierarchyEntry> = vec![];
+
+    for (idx, declaration) in index.types[type_id].declarations.iter().enumerate() {
+        for &(dependency, _, kind, _) in &declaration.dependencies {
+            let Some(kind) = relationship(index.classify(kind, dependency)) else {
+                continue;
+            };
+            let dependency = index.resolve_alias(dependency);
+
+            if parents.iter().any(|parent| parent.type_id == dependency) {
+                continue;
+            }
+
+            parents.push(HierarchyEntry {
+                type_id: dependency,
+                declaration: (type_id, idx),
+                kind,
+                depth,
+            });
+        }
+    }
+
+    parents.sort_by_key(|parent| parent.kind);
+
+    // Types related directly are listed here rather than further down
+    parents.retain(|parent| visited.insert(parent.type_id));
+
+    for parent in parents {
+        let parent_id = parent.type_id;
+        entries.push(parent);
+
+        walk_up(index, parent_id, depth + 1, visited, entries);
+    }
+}
+
+fn walk_down(
+    index: &Index,
+    type_id: TypeId,
+    depth: usize,
+    visited: &mut HashSet<TypeId>,
+    entries: &mut Vec<HierarchyEntry>,
+) {
+    let mut children: Vec<HierarchyEntry> = vec![];
+
+    let dependents = aliases(index, type_id)
+        .into_iter()
+        .flat_map(|alias| index.dependents_of(alias).iter().map(move |&d| (alias, d)));
+
+    for (alias, (dependent, idx)) in dependents {
+        let declaration = &index.types[dependent].declarations[idx];
+        let Some(kind) = index
+            .reference_kinds(declaration, alias)
+            .into_iter()
+            .find_map(relationship)
+        else {
+            continue;
+        };
+
+        if children.iter().any(|child| child.type_id == dependent) {
+            continue;
+        }
+
+        children.push(HierarchyEntry {
+            type_id: dependent,
+            declaration: (dependent, idx),
+            kind,
+            depth,
+        });
+    }
+
+    children.sort_by_key(|child| (child.kind, &index.types[child.type_id].name));
+
+    // Types related directly are listed here rather than further down
+    children.retain(|child| visited.insert(child.type_id));
+
+    for child in children {
+        let child_id = child.type_id;
+        entries.push(child);
+
+        walk_down(index, child_id, depth + 1, visited, entries);
+    }
+}
+
+// The type and the typealiases standing for it, transitively
+fn aliases(index: &Index, type_id: TypeId) -> Vec<TypeId> {
+    let mut aliases = vec![type_id];
+    let mut idx = 0;
+
+    while idx < aliases.len() {
+        for &(dependent, declaration) in index.dependents_of(aliases[idx]) {
+            let declaration = &index.types[dependent].declarations[declaration];
+            let is_alias = declaration.kind == Kind::TypeAlias
+                && declaration
+                    .dependencies
+                    .iter()
+                    .any(|&(aliased, _, kind, _)| {
+                        aliased == aliases[idx] && kind == ReferenceKind::Alias
+                    });
+
+            if is_alias && !aliases.contains(&dependent) {
+                aliases.push(dependent);
+            }
+        }
+
+        idx += 1;
+    }
+
+    aliases
+}
+
+fn relationship(kind: ReferenceKind) -> Option<ReferenceKind> {
+    match kind {
+        ReferenceKind::Inheritance | ReferenceKind::Conformance => Some(kind),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Point;
+
+    use super::*;
+    use crate::index::Confidence;
+
+    fn hierarchy() -> Index {
+        let mut index = Index::new();
+        index.add_declaration(
+            "LoginView",
+            Kind::Class,
+            "./LoginView.swift",
+            Point::new(1, 6),
+            &[
+                (
+                    "Themed",
+                    &Point::new(1, 25),
+                    ReferenceKind::Conformance,
+                    Confidence::High,
+                ),
+                (
+                    "BaseView",
+                    &Point::new(1, 18),
+                    ReferenceKind::Inheritance,
+                    Confidence::High,
+                ),
+                (
+                    "Session",
+                    &Point::new(2, 10),
+                    ReferenceKind::StoredProperty,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "BaseView",
+            Kind::Class,
+            "./BaseView.swift",
+            Point::new(1, 6),
+            &[
+                (
+                    "UIView",
+                    &Point::new(1, 17),
+                    ReferenceKind::Inheritance,
+                    Confidence::High,
+                ),
+                (
+                    "Themed",
+                    &Point::new(1, 25),
+                    ReferenceKind::Conformance,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "Themed",
+            Kind::Protocol,
+            "./Themed.swift",
+            Point::new(1, 9),
+            &[],
+        );
+        index.add_declaration(
+            "Session",
+            Kind::Struct,
+            "./Session.swift",
+            Point::new(1, 7),
+            &[],
+        );
+        index.add_declaration(
+            "Session",
+            Kind::Extension,
+            "./Session+Themed.swift",
+            Point::new(1, 10),
+            &[(
+                "Themed",
+                &Point::new(1, 19),
+                ReferenceKind::Conformance,
+                Confidence::High,
+            )],
+        );
+
+        index
+    }
+
+    fn names(index: &Index, entries: Vec<HierarchyEntry>) -> Vec<(&str, ReferenceKind, usize)> {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let t = index.get_type(entry.type_id).unwrap();
+
+                (t.name.as_str(), entry.kind, entry.depth)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_superclasses_before_protocols() {
+        let index = hierarchy();
+        let actual = supertypes(&index, index.type_id("LoginView").unwrap());
+
+        assert_eq!(
+            names(&index, actual),
+            vec![
+                ("BaseView", ReferenceKind::Inheritance, 0),
+                ("UIView", ReferenceKind::Inheritance, 1),
+                ("Themed", ReferenceKind::Conformance, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_conformances_added_in_extensions() {
+        let index = hierarchy();
+        let actual = subtypes(&index, index.type_id("Themed").unwrap());
+
+        assert_eq!(
+            names(&index, actual),
+            vec![
+                ("BaseView", ReferenceKind::Conformance, 0),
+                ("LoginView", ReferenceKind::Conformance, 0),
+                ("Session", ReferenceKind::Conformance, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_typealiases() {
+        let mut index = hierarchy();
+        index.add_declaration(
+            "View",
+            Kind::TypeAlias,
+            "./View.swift",
+            Point::new(1, 10),
+            &[(
+                "BaseView",
+                &Point::new(1, 17),
+                ReferenceKind::Alias,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "SettingsView",
+            Kind::Class,
+            "./SettingsView.swift",
+            Point::new(1, 6),
+            &[(
+                "View",
+                &Point::new(1, 21),
+                ReferenceKind::Inheritance,
+                Confidence::High,
+            )],
+        );
+
+        let up = supertypes(&index, index.type_id("SettingsView").unwrap());
+        let down = subtypes(&index, index.type_id("BaseView").unwrap());
+
+        assert_eq!(
+            names(&index, up),
+            vec![
+                ("BaseView", ReferenceKind::Inheritance, 0),
+                ("UIView", ReferenceKind::Inheritance, 1),
+                ("Themed", ReferenceKind::Conformance, 1),
+            ]
+        );
+        assert_eq!(
+            names(&index, down),
+            vec![
+                ("LoginView", ReferenceKind::Inheritance, 0),
+                ("SettingsView", ReferenceKind::Inheritance, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_subclasses_of_subclasses() {
+        let index = hierarchy();
+        let actual = subtypes(&index, index.type_id("UIView").unwrap());
+
+        assert_eq!(
+            names(&index, actual),
+            vec![
+                ("BaseView", ReferenceKind::Inheritance, 0),
+                ("LoginView", ReferenceKind::Inheritance, 1),
+            ]
+        );
+    }
+}
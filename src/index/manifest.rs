@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, HashSet};
+
+use super::{Index, PackageId, PackageReference, TargetDependency, TargetId};
+
+// Number of example references kept for each missing dependency
+const EXAMPLE_LIMIT: usize = 3;
+
+/// Differences between the target dependencies declared in Package.swift manifests
+/// and the references between the targets' types
+#[derive(Debug, Default, PartialEq)]
+pub struct ManifestReport {
+    pub missing: Vec<MissingDependency>,
+    pub unused: Vec<UnusedDependency>,
+}
+
+/// Types of a target referencing types of another target it doesn't depend on
+#[derive(Debug, PartialEq)]
+pub struct MissingDependency {
+    pub from: TargetId,
+    pub to: TargetId,
+    /// Number of references
+    pub count: usize,
+    /// The first few references, in index order
+    pub examples: Vec<PackageReference>,
+}
+
+/// A declared dependency none of the target's types reference
+#[derive(Debug, PartialEq)]
+pub struct UnusedDependency {
+    pub target: TargetId,
+    /// Index into the target's dependencies
+    pub dependency: usize,
+}
+
+/// Compare declared target dependencies with the references between targets.
+/// Dependencies on products of packages which weren't scanned are never reported unused.
+pub fn check(index: &Index) -> ManifestReport {
+    let type_targets: Vec<Option<TargetId>> = index
+        .types
+        .iter()
+        .map(|t| index.target_id(t.primary_declaration()?))
+        .collect();
+
+    // References between different targets
+    let mut used: BTreeMap<(TargetId, TargetId), MissingDependency> = BTreeMap::new();
+
+    for (type_id, t) in index.types.iter().enumerate() {
+        for (idx, declaration) in t.declarations.iter().enumerate() {
+            let Some(from) = index.target_id(declaration) else {
+                continue;
+            };
+
+            for &(dependency, point, _, _) in &declaration.dependencies {
+                let Some(to) = type_targets[dependency] else {
+                    continue;
+                };
+
+                if from == to {
+                    continue;
+                }
+
+                let edge = used.entry((from, to)).or_insert_with(|| MissingDependency {
+                    from,
+                    to,
+                    count: 0,
+                    examples: vec![],
+                });
+
+                edge.count += 1;
+
+                if edge.examples.len() < EXAMPLE_LIMIT {
+                    edge.examples.push(PackageReference {
+                        from: type_id,
+                        declaration: idx,
+                        to: dependency,
+                        point,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut report = ManifestReport::default();
+    let mut declared: HashSet<(TargetId, TargetId)> = HashSet::new();
+
+    for (package_id, package) in index.packages.iter().enumerate() {
+        for (idx, target) in package.targets.iter().enumerate() {
+            let target_id = (package_id, idx);
+
+            for (dependency_idx, dependency) in target.dependencies.iter().enumerate() {
+                let resolved = resolve(index, package_id, dependency);
+
+                if !resolved.is_empty()
+                    && !resolved
+                        .iter()
+                        .any(|&to| used.contains_key(&(target_id, to)))
+                {
+                    report.unused.push(UnusedDependency {
+                        target: target_id,
+                        dependency: dependency_idx,
+                    });
+                }
+
+                declared.extend(resolved.into_iter().map(|to| (target_id, to)));
+            }
+        }
+    }
+
+    report.missing = used
+        .into_values()
+        .filter(|edge| !declared.contains(&(edge.from, edge.to)))
+        .collect();
+
+    report
+}
+
+// Targets a dependency makes available: a target of the same package, or the targets
+// of a product
+fn resolve(index: &Index, package_id: PackageId, dependency: &TargetDependency) -> Vec<TargetId> {
+    let packages: Vec<PackageId> = match &dependency.package {
+        None => {
+            let targets = &index.packages[package_id].targets;
+
+            if let Some(idx) = targets.iter().position(|t| t.name == dependency.name) {
+                return vec![(package_id, idx)];
+            }
+
+            // Products can be referred to by name alone
+            (0..index.packages.len()).collect()
+        }
+        Some(identity) => (0..index.packages.len())
+            .filter(|&id| index.packages[id].is_identified_by(identity))
+            .collect(),
+    };
+
+    let mut targets = vec![];
+
+    for id in packages {
+        let package = &index.packages[id];
+
+        for product in package
+            .products
+            .iter()
+            .filter(|product| product.name == dependency.name)
+        {
+            targets.extend(product.targets.iter().filter_map(|name| {
+                let idx = package.targets.iter().position(|t| &t.name == name)?;
+
+                Some((id, idx))
+            }));
+        }
+    }
+
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Point;
+
+    use super::*;
+    use crate::index::{Confidence, Kind, ReferenceKind, TargetKind};
+
+    fn add(index: &mut Index, name: &str, file: &str, references: &[&str]) {
+        let point = Point::new(1, 1);
+        let references: Vec<_> = references
+            .iter()
+            .map(|r| (*r, &point, ReferenceKind::BodyCall, Confidence::High))
+            .collect();
+
+        index.add_declaration(name, Kind::Struct, file, point, &references);
+    }
+
+    fn packages() -> Index {
+        let mut index = Index::new();
+
+        let utils = index.add_package("Utilities", "./Utils");
+        index.add_product(utils, "Utils", &["Utils"]);
+        index.add_target(utils, "Utils", TargetKind::Regular, None, &[]);
+
+        let core = index.add_package("Core", "./Core");
+        index.add_package_dependency(core, "Utils", Some("../Utils"));
+        index.add_target(
+            core,
+            "Core",
+            TargetKind::Regular,
+            None,
+            &[("Utils", Some("Utils")), ("Logging", Some("swift-log"))],
+        );
+        index.add_target(core, "Network", TargetKind::Regular, None, &[]);
+        index.add_target(
+            core,
+            "CoreTests",
+            TargetKind::Test,
+            None,
+            &[("Core", None), ("Network", None)],
+        );
+
+        add(
+            &mut index,
+            "Formatter",
+            "./Utils/Sources/Utils/Formatter.swift",
+            &[],
+        );
+        add(
+            &mut index,
+            "Client",
+            "./Core/Sources/Network/Client.swift",
+            &[],
+        );
+        index.set_file_imports("./Core/Sources/Core/Model.swift", &["Utils", "Network"]);
+        index.set_file_imports("./Core/Tests/CoreTests/ModelTests.swift", &["Core"]);
+
+        add(
+            &mut index,
+            "Model",
+            "./Core/Sources/Core/Model.swift",
+            &["Formatter", "Client"],
+        );
+        add(
+            &mut index,
+            "ModelTests",
+            "./Core/Tests/CoreTests/ModelTests.swift",
+            &["Model"],
+        );
+
+        index
+    }
+
+    #[test]
+    fn reports_missing_dependencies() {
+        let index = packages();
+        let core = index.package_id_by_name("Core").unwrap();
+
+        let report = index.check_manifests();
+
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].from, (core, 0));
+        assert_eq!(report.missing[0].to, (core, 1));
+        assert_eq!(report.missing[0].count, 1);
+    }
+
+    #[test]
+    fn reports_unused_dependencies() {
+        let index = packages();
+        let core = index.package_id_by_name("Core").unwrap();
+
+        let report = index.check_manifests();
+
+        // Logging is a product of a package which wasn't scanned
+        assert_eq!(
+            report.unused,
+            vec![UnusedDependency {
+                target: (core, 2),
+                dependency: 1,
+            }]
+        );
+    }
+}
@@ -1,12 +1,27 @@
 mod cursor;
+mod cycles;
+mod dependents;
+mod hierarchy;
+mod manifest;
+mod packages;
+mod paths;
+mod persist;
+mod resolve;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
+use clap::ValueEnum;
 use patricia_tree::GenericPatriciaMap;
+use serde::{Deserialize, Serialize};
 use tree_sitter::Point;
 
 pub use cursor::{IndexCursor, IndexItem};
+pub use dependents::DependentsCursor;
+pub use hierarchy::HierarchyEntry;
+pub use manifest::ManifestReport;
+pub use packages::{PackageEdge, PackageReference};
+pub use resolve::AmbiguousReference;
 
 // TODO consider pros/cons of using Paths and PathBufs
 
@@ -14,42 +29,190 @@ pub use cursor::{IndexCursor, IndexItem};
 pub type PackageId = usize;
 pub type FileId = usize;
 pub type TypeId = usize;
+// Targets are identified by their package and an offset into the package's targets
+pub type TargetId = (PackageId, usize);
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
     pub name: String,
+    /// Swift module (target) the type is declared in, or the TypeScript module (file),
+    /// None for types outside of any target and for types only referenced
+    pub module: Option<String>,
     pub declarations: Vec<Declaration>, // A type may be extended in multiple places
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TypeOrigin {
     Local,
     External,
 }
 
 impl Type {
-    fn origin(&self) -> TypeOrigin {
+    pub fn origin(&self) -> TypeOrigin {
         if self.declarations.is_empty() {
             TypeOrigin::External
         } else {
             TypeOrigin::Local
         }
     }
+
+    /// The declaration introducing the type, or the first extension of types only
+    /// extended in the codebase
+    pub fn primary_declaration(&self) -> Option<&Declaration> {
+        self.declarations
+            .iter()
+            .find(|declaration| declaration.kind != Kind::Extension)
+            .or(self.declarations.first())
+    }
+
+    /// Name of the type qualified with its module, e.g. Core.Router
+    pub fn qualified_name(&self) -> String {
+        match &self.module {
+            Some(module) => format!("{}.{}", module, self.name),
+            None => self.name.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Package {
     name: String,
     path_prefix: String,
+    /// Products the package vends, as declared in Package.swift
+    pub products: Vec<Product>,
+    /// Other packages this package depends on
+    pub dependencies: Vec<PackageDependency>,
+    pub targets: Vec<Target>,
+}
+
+impl Package {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path prefix of the files in the package, ending with a '/'
+    pub fn path_prefix(&self) -> &str {
+        &self.path_prefix
+    }
+
+    /// Whether a package dependency refers to this package, by its name or identity
+    pub fn is_identified_by(&self, identity: &str) -> bool {
+        let directory = self
+            .path_prefix
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+
+        self.name == identity || directory == identity
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Product {
+    pub name: String,
+    /// Names of the targets making up the product
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PackageDependency {
+    /// Package identity, the last path component of its URL or path
+    pub name: String,
+    /// Path of a local package, relative to the depending package
+    pub path: Option<String>,
+}
+
+/// A Swift package manager target
+#[derive(Debug, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub kind: TargetKind,
+    path_prefix: String,
+    pub dependencies: Vec<TargetDependency>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Regular,
+    Executable,
+    Test,
+    Macro,
+    Plugin,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// A dependency of a target on another target or a product of another package
+#[derive(Debug, PartialEq)]
+pub struct TargetDependency {
+    pub name: String,
+    /// Package vending the product, None for targets of the same package
+    pub package: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Kind {
     Struct,
     Enum,
     Class,
+    Actor,
     Protocol,
     Extension,
+    TypeAlias,
+    /// Associated type of a protocol
+    AssociatedType,
+    /// Top-level function
+    Function,
+    /// Global variable
+    Variable,
+    /// Global constant
+    Constant,
+    Macro,
+}
+
+impl Kind {
+    /// Whether declarations of the kind introduce a type, rather than e.g. a function
+    pub fn is_type(self) -> bool {
+        !matches!(
+            self,
+            Kind::Function | Kind::Variable | Kind::Constant | Kind::Macro
+        )
+    }
+}
+
+/// Relationship a reference creates between the declaring type and the referenced type
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceKind {
+    /// Superclass of a class
+    Inheritance,
+    /// Protocol adopted by a type or refined by a protocol
+    Conformance,
+    /// Type of a stored property or an enum case associated value
+    StoredProperty,
+    /// Parameter or return type of a function, initializer or subscript
+    FunctionSignature,
+    /// Generic parameter or constraint
+    GenericConstraint,
+    /// Type a typealias stands for
+    Alias,
+    /// Use in a function body, computed property or initial value
+    BodyCall,
+}
+
+/// How certain it is that a reference is to the type
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    /// Name used in an expression, e.g. `Formatter()`, which resolves to a type
+    /// declared in the codebase
+    Medium,
+    /// Name in a type position, e.g. a type annotation or an inheritance clause
+    High,
 }
 
 /// Type declaration
@@ -61,15 +224,19 @@ pub struct Declaration {
     pub point: Point,
     // File in which the declaration is
     file: FileId,
-    // Types the declaration uses and locations of the references
-    dependencies: Vec<(TypeId, Point)>,
+    // Types the declaration uses, locations of the references, their kinds as
+    // classified by the parser and the confidence in them
+    dependencies: Vec<(TypeId, Point, ReferenceKind, Confidence)>,
+    // Names used in expressions which don't resolve to a declared type, e.g. enum
+    // cases or constants, kept to resolve them again when other files are scanned
+    unresolved: Vec<(String, Point, ReferenceKind)>,
 }
 
 impl Declaration {
-    pub fn dependencies(&self) -> HashMap<TypeId, Vec<Point>> {
-        let mut deps = HashMap::new();
+    pub fn dependencies(&self) -> BTreeMap<TypeId, Vec<Point>> {
+        let mut deps = BTreeMap::new();
 
-        for (id, point) in &self.dependencies {
+        for (id, point, _, _) in &self.dependencies {
             deps.entry(*id).or_insert(vec![]).push(*point)
         }
 
@@ -77,6 +244,25 @@ impl Declaration {
     }
 }
 
+/// State of a file when it was scanned, used to skip unchanged files when scanning again
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct FileStamp {
+    /// Modification time in nanoseconds since the Unix epoch
+    pub modified: u64,
+    pub size: u64,
+    /// Hash of the file contents
+    pub hash: u64,
+}
+
+/// Part of a file which failed to parse
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SyntaxError {
+    pub start: Point,
+    pub end: Point,
+    /// Kind of a node the parser assumed to be missing, e.g. "}"
+    pub missing: Option<String>,
+}
+
 #[derive(Debug, Default)]
 pub struct Index {
     // Storage
@@ -87,8 +273,29 @@ pub struct Index {
     // Indexes
     file_ids: HashMap<String, FileId>,
     package_ids: HashMap<String, PackageId>,
-    type_ids: HashMap<String, TypeId>,
+    // Types by name, one for each module declaring a type of the name
+    type_ids: HashMap<String, Vec<TypeId>>,
     packages_by_path: GenericPatriciaMap<String, PackageId>,
+    targets_by_path: GenericPatriciaMap<String, TargetId>,
+    // Reverse edges: for each type, the declarations referencing it as
+    // (type ID, index into the type's declarations)
+    dependents: HashMap<TypeId, Vec<(TypeId, usize)>>,
+    // State of the scanned files
+    file_stamps: HashMap<FileId, FileStamp>,
+    // Package each file belongs to, by the longest matching package path prefix
+    file_packages: HashMap<FileId, PackageId>,
+    // Target each file belongs to, if it is in one of its package's targets
+    file_targets: HashMap<FileId, TargetId>,
+    // Module of each file which is a module by itself, e.g. a TypeScript source
+    file_modules: HashMap<FileId, String>,
+    // Modules imported by each file
+    file_imports: HashMap<FileId, Vec<String>>,
+    // Objective-C bridging headers, whose imports are visible to the files next to them
+    bridging_headers: Vec<FileId>,
+    // Syntax errors in the scanned files, for files with any
+    file_errors: HashMap<FileId, Vec<SyntaxError>>,
+    // Hash of the queries the files were parsed with
+    queries_hash: Option<u64>,
 }
 
 impl Index {
@@ -100,15 +307,32 @@ impl Index {
             package_ids: HashMap::new(),
             type_ids: HashMap::new(),
             packages_by_path: GenericPatriciaMap::new(),
+            targets_by_path: GenericPatriciaMap::new(),
             file_ids: HashMap::new(),
+            dependents: HashMap::new(),
+            file_stamps: HashMap::new(),
+            file_packages: HashMap::new(),
+            file_targets: HashMap::new(),
+            file_modules: HashMap::new(),
+            file_imports: HashMap::new(),
+            bridging_headers: vec![],
+            file_errors: HashMap::new(),
+            queries_hash: None,
         }
     }
 
     // Reading from index
 
-    /// Geta type ID for a string name
+    /// Get a type ID for a string name: the type of that name outside of any module,
+    /// or the only type of that name
     pub fn type_id(&self, name: &str) -> Option<TypeId> {
-        self.type_ids.get(name).copied()
+        let type_ids = self.type_ids.get(name)?;
+
+        type_ids
+            .iter()
+            .copied()
+            .find(|&type_id| self.types[type_id].module.is_none())
+            .or_else(|| (type_ids.len() == 1).then_some(type_ids[0]))
     }
 
     /// Get a type definition for a type ID
@@ -116,30 +340,404 @@ impl Index {
         self.types.get(type_id)
     }
 
+    /// Iterate over all known types and their IDs
+    pub fn types(&self) -> impl Iterator<Item = (TypeId, &Type)> {
+        self.types.iter().enumerate()
+    }
+
     /// Find a file path where declaration was made
     pub fn file_path(&self, declaration: &Declaration) -> Option<String> {
         self.files.get(declaration.file).cloned()
     }
 
-    pub fn walk(&self, type_name: &str) -> anyhow::Result<IndexCursor> {
-        let type_id = self
-            .type_id(type_name)
-            .ok_or_else(|| anyhow!("Type name {} not found in the index.", type_name))?;
+    /// Paths and states of all scanned files
+    pub fn file_stamps(&self) -> HashMap<String, FileStamp> {
+        self.file_stamps
+            .iter()
+            .map(|(&file_id, &stamp)| (self.files[file_id].clone(), stamp))
+            .collect()
+    }
+
+    /// Hash of the queries the scanned files were parsed with, if any were scanned
+    pub fn queries_hash(&self) -> Option<u64> {
+        self.queries_hash
+    }
+
+    /// Syntax errors of the scanned files which have any, sorted by path
+    pub fn syntax_errors(&self) -> Vec<(&str, &[SyntaxError])> {
+        let mut errors: Vec<_> = self
+            .file_errors
+            .iter()
+            .map(|(&file_id, errors)| (self.files[file_id].as_str(), errors.as_slice()))
+            .collect();
+
+        errors.sort_by_key(|&(path, _)| path);
+
+        errors
+    }
+
+    /// Find the package the declaration was made in
+    pub fn package_id(&self, declaration: &Declaration) -> Option<PackageId> {
+        self.file_packages.get(&declaration.file).copied()
+    }
+
+    /// Find the name of the package the declaration was made in
+    pub fn package_name(&self, declaration: &Declaration) -> Option<&str> {
+        let package_id = self.package_id(declaration)?;
+
+        self.packages.get(package_id).map(|p| p.name.as_ref())
+    }
+
+    /// Find the target the declaration was made in
+    pub fn target_id(&self, declaration: &Declaration) -> Option<TargetId> {
+        self.file_targets.get(&declaration.file).copied()
+    }
+
+    /// Find the name of the target the declaration was made in
+    pub fn target_name(&self, declaration: &Declaration) -> Option<&str> {
+        let target = self.get_target(self.target_id(declaration)?)?;
+
+        Some(target.name.as_ref())
+    }
+
+    pub fn get_target(&self, (package_id, idx): TargetId) -> Option<&Target> {
+        self.packages.get(package_id)?.targets.get(idx)
+    }
+
+    /// Get a package ID for a package name
+    pub fn package_id_by_name(&self, name: &str) -> Option<PackageId> {
+        self.package_ids.get(name).copied()
+    }
+
+    pub fn get_package(&self, package_id: PackageId) -> Option<&Package> {
+        self.packages.get(package_id)
+    }
+
+    /// All known packages, in the order they were added
+    pub fn packages(&self) -> impl Iterator<Item = (PackageId, &Package)> {
+        self.packages.iter().enumerate()
+    }
+
+    /// Dependencies between packages, aggregated from the references between their types
+    pub fn package_graph(&self) -> Vec<PackageEdge> {
+        packages::package_graph(self)
+    }
+
+    /// Find target dependencies missing from, or unused but declared in package manifests
+    pub fn check_manifests(&self) -> ManifestReport {
+        manifest::check(self)
+    }
+
+    /// Kinds of the references a declaration makes to a type
+    pub fn reference_kinds(
+        &self,
+        declaration: &Declaration,
+        type_id: TypeId,
+    ) -> BTreeSet<ReferenceKind> {
+        declaration
+            .dependencies
+            .iter()
+            .filter(|(dependency, _, _, _)| *dependency == type_id)
+            .map(|&(dependency, _, kind, _)| self.classify(kind, dependency))
+            .collect()
+    }
+
+    /// Confidence in the most certain reference a declaration makes to a type
+    pub fn reference_confidence(
+        &self,
+        declaration: &Declaration,
+        type_id: TypeId,
+    ) -> Option<Confidence> {
+        declaration
+            .dependencies
+            .iter()
+            .filter(|(dependency, _, _, _)| *dependency == type_id)
+            .map(|&(_, _, _, confidence)| confidence)
+            .max()
+    }
+
+    // The parser can only guess which supertype is a superclass, correct the guess
+    // for types declared in the codebase
+    fn classify(&self, kind: ReferenceKind, type_id: TypeId) -> ReferenceKind {
+        if !matches!(
+            kind,
+            ReferenceKind::Inheritance | ReferenceKind::Conformance
+        ) {
+            return kind;
+        }
+
+        let declared_kind = self.types[self.resolve_alias(type_id)]
+            .primary_declaration()
+            .map(|declaration| declaration.kind);
+
+        match declared_kind {
+            Some(Kind::Class) => ReferenceKind::Inheritance,
+            Some(Kind::Extension) | None => kind,
+            Some(_) => ReferenceKind::Conformance,
+        }
+    }
+
+    /// Follow typealiases to the type they stand for, other types resolve to themselves
+    pub fn resolve_alias(&self, type_id: TypeId) -> TypeId {
+        let mut resolved = vec![type_id];
+
+        loop {
+            let current = *resolved.last().expect("resolved types are never empty");
+            let aliased = self.types[current]
+                .primary_declaration()
+                .filter(|declaration| declaration.kind == Kind::TypeAlias)
+                .and_then(|declaration| {
+                    declaration
+                        .dependencies
+                        .iter()
+                        .find(|(_, _, kind, _)| *kind == ReferenceKind::Alias)
+                })
+                .map(|&(aliased, _, _, _)| aliased);
+
+            match aliased {
+                // Aliases referring to each other don't resolve any further
+                Some(aliased) if !resolved.contains(&aliased) => resolved.push(aliased),
+                _ => return current,
+            }
+        }
+    }
+
+    pub fn walk(&self, type_name: &str) -> anyhow::Result<IndexCursor<'_>> {
+        let type_id = self.existing_type_id(type_name)?;
 
         Ok(IndexCursor::new(self, type_id))
     }
 
+    /// Declarations referencing a type, as (type ID, index into the type's declarations)
+    pub fn dependents_of(&self, type_id: TypeId) -> &[(TypeId, usize)] {
+        self.dependents
+            .get(&type_id)
+            .map(|edges| edges.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn walk_dependents(&self, type_name: &str) -> anyhow::Result<DependentsCursor<'_>> {
+        let type_id = self.existing_type_id(type_name)?;
+
+        Ok(DependentsCursor::new(self, type_id))
+    }
+
+    /// Find groups of types which depend on each other, directly or transitively
+    /// Each cycle is sorted by type name, types only referencing themselves are not included
+    pub fn cycles(&self) -> Vec<Vec<TypeId>> {
+        let mut cycles: Vec<_> = cycles::strongly_connected_components(self)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|mut component| {
+                component.sort_by_key(|&type_id| &self.types[type_id].name);
+                component
+            })
+            .collect();
+
+        cycles.sort_by_key(|component| &self.types[component[0]].name);
+
+        cycles
+    }
+
+    /// Packages the types are declared in, declarations made outside of any package
+    /// are left out rather than counted as a package of their own
+    pub fn declaring_packages(&self, type_ids: &[TypeId]) -> BTreeSet<PackageId> {
+        type_ids
+            .iter()
+            .filter_map(|&type_id| self.types.get(type_id))
+            .flat_map(|t| &t.declarations)
+            .filter_map(|declaration| self.package_id(declaration))
+            .collect()
+    }
+
+    /// Superclasses and protocols adopted by a type, transitively
+    pub fn supertypes(&self, type_name: &str) -> anyhow::Result<Vec<HierarchyEntry>> {
+        Ok(hierarchy::supertypes(
+            self,
+            self.existing_type_id(type_name)?,
+        ))
+    }
+
+    /// Subclasses of a type and types conforming to it, transitively
+    pub fn subtypes(&self, type_name: &str) -> anyhow::Result<Vec<HierarchyEntry>> {
+        Ok(hierarchy::subtypes(self, self.existing_type_id(type_name)?))
+    }
+
+    /// Find the shortest chain of dependencies leading from one type to another
+    pub fn shortest_path(&self, from: &str, to: &str) -> anyhow::Result<Option<Vec<TypeId>>> {
+        let (from, to) = self.path_ends(from, to)?;
+
+        Ok(paths::shortest_path(self, from, to))
+    }
+
+    /// Find up to `limit` chains of dependencies leading from one type to another,
+    /// never visiting a type twice. Shorter paths are found first.
+    pub fn simple_paths(
+        &self,
+        from: &str,
+        to: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Vec<TypeId>>> {
+        let (from, to) = self.path_ends(from, to)?;
+
+        Ok(paths::simple_paths(self, from, to, limit))
+    }
+
+    // Types a path leads from and to, which must be different types, paths leading
+    // back to a type are cycles
+    fn path_ends(&self, from: &str, to: &str) -> anyhow::Result<(TypeId, TypeId)> {
+        let (from_id, to_id) = (self.existing_type_id(from)?, self.existing_type_id(to)?);
+
+        if from_id == to_id {
+            bail!(
+                "{} and {} are the same type, use `drake cycles` to find dependencies leading back to it.",
+                from,
+                to
+            );
+        }
+
+        Ok((from_id, to_id))
+    }
+
+    // Find a type by its name, or qualified with its module or enclosing types as long
+    // as only one declared type matches
+    fn existing_type_id(&self, name: &str) -> anyhow::Result<TypeId> {
+        let declared = |type_id: &TypeId| self.types[*type_id].origin() == TypeOrigin::Local;
+
+        let mut matches: Vec<TypeId> = self
+            .type_ids
+            .get(name)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(declared)
+            .collect();
+
+        // Module names are followed by a type name, which may be nested, or are paths
+        // themselves, e.g. ./web/src/router.Router
+        let qualified = [name.split_once('.'), name.rsplit_once('.')];
+
+        for (module, rest) in qualified.into_iter().flatten() {
+            if !matches.is_empty() {
+                break;
+            }
+
+            matches.extend(
+                self.type_ids
+                    .get(rest)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|&type_id| self.types[type_id].module.as_deref() == Some(module))
+                    .filter(declared),
+            );
+        }
+
+        if matches.is_empty() {
+            let suffix = format!(".{}", name);
+
+            matches.extend(
+                self.types()
+                    .filter(|(_, t)| t.name.ends_with(&suffix))
+                    .map(|(type_id, _)| type_id)
+                    .filter(declared),
+            );
+        }
+
+        match matches.len() {
+            0 => self
+                .type_id(name)
+                .ok_or_else(|| anyhow!("Type name {} not found in the index.", name)),
+            1 => Ok(matches[0]),
+            _ => {
+                let mut names: Vec<_> = matches
+                    .iter()
+                    .map(|&type_id| self.types[type_id].qualified_name())
+                    .collect();
+                names.sort();
+
+                bail!(
+                    "Type name {} is ambiguous, use one of {}.",
+                    name,
+                    names.join(", ")
+                )
+            }
+        }
+    }
+
+    /// References to names declared in several of the modules imported by the file
+    /// making the reference
+    pub fn ambiguous_references(&self) -> Vec<AmbiguousReference> {
+        resolve::ambiguous_references(self)
+    }
+
+    // Module of a file, the file itself if it is a module, or the target it belongs to
+    fn module(&self, file: FileId) -> Option<&str> {
+        if let Some(module) = self.file_modules.get(&file) {
+            return Some(module);
+        }
+
+        let target = self.get_target(*self.file_targets.get(&file)?)?;
+
+        Some(&target.name)
+    }
+
+    fn is_module(&self, name: &str) -> bool {
+        self.packages
+            .iter()
+            .any(|package| package.targets.iter().any(|target| target.name == name))
+    }
+
+    // Unique types referenced by any declaration of a type
+    fn successors(&self, type_id: TypeId) -> Vec<TypeId> {
+        let mut successors = vec![];
+
+        for declaration in &self.types[type_id].declarations {
+            for (dependency, _, _, _) in &declaration.dependencies {
+                if !successors.contains(dependency) {
+                    successors.push(*dependency);
+                }
+            }
+        }
+
+        successors
+    }
+
     // Building the index
     // TODO do I need an IndexBuilder...?
 
     /// Add a package to the index
-    pub fn add_package(&mut self, name: &str, path_prefix: &str) {
+    /// Adding a known package again renames it and forgets its products, dependencies
+    /// and targets, so that they can be added again from the manifest.
+    pub fn add_package(&mut self, name: &str, path_prefix: &str) -> PackageId {
         let name = name.to_string();
-        let path_prefix = path_prefix.to_string();
+        let path_prefix = directory_prefix(path_prefix);
+
+        if let Some(&package_id) = self.packages_by_path.get(&path_prefix) {
+            let package = &mut self.packages[package_id];
+
+            for target in package.targets.drain(..) {
+                self.targets_by_path.remove(&target.path_prefix);
+            }
+
+            package.products.clear();
+            package.dependencies.clear();
+
+            self.package_ids.remove(&package.name);
+            self.package_ids.insert(name.clone(), package_id);
+            package.name = name;
+
+            self.assign_packages();
+
+            return package_id;
+        }
 
         let package = Package {
             name: name.to_string(),
             path_prefix: path_prefix.clone(),
+            products: vec![],
+            dependencies: vec![],
+            targets: vec![],
         };
 
         self.packages.push(package);
@@ -147,6 +745,81 @@ impl Index {
 
         self.package_ids.insert(name.to_string(), package_id);
         self.packages_by_path.insert(path_prefix, package_id);
+
+        // The new package may be a better match for known files
+        self.assign_packages();
+
+        package_id
+    }
+
+    /// Add a product vended by a package
+    pub fn add_product(&mut self, package_id: PackageId, name: &str, targets: &[&str]) {
+        self.packages[package_id].products.push(Product {
+            name: name.to_string(),
+            targets: targets.iter().map(|target| target.to_string()).collect(),
+        });
+    }
+
+    /// Add a dependency of a package on another package
+    pub fn add_package_dependency(
+        &mut self,
+        package_id: PackageId,
+        name: &str,
+        path: Option<&str>,
+    ) {
+        self.packages[package_id]
+            .dependencies
+            .push(PackageDependency {
+                name: name.to_string(),
+                path: path.map(str::to_string),
+            });
+    }
+
+    /// Add a target to a package. Without an explicit path, the target is expected
+    /// in the Swift package manager's default location. Dependencies are pairs
+    /// of a target or product name and the package vending the product.
+    pub fn add_target(
+        &mut self,
+        package_id: PackageId,
+        name: &str,
+        kind: TargetKind,
+        path: Option<&str>,
+        dependencies: &[(&str, Option<&str>)],
+    ) -> TargetId {
+        let package = &mut self.packages[package_id];
+
+        let path = match (path, kind) {
+            (Some(path), _) => path
+                .trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string(),
+            (None, TargetKind::Test) => format!("Tests/{}", name),
+            (None, TargetKind::Plugin) => format!("Plugins/{}", name),
+            (None, _) => format!("Sources/{}", name),
+        };
+        let path_prefix = match path.as_str() {
+            "" | "." => package.path_prefix.clone(),
+            path => directory_prefix(&format!("{}{}", package.path_prefix, path)),
+        };
+
+        package.targets.push(Target {
+            name: name.to_string(),
+            kind,
+            path_prefix: path_prefix.clone(),
+            dependencies: dependencies
+                .iter()
+                .map(|(name, package)| TargetDependency {
+                    name: name.to_string(),
+                    package: package.map(str::to_string),
+                })
+                .collect(),
+        });
+        let target_id = (package_id, package.targets.len() - 1);
+
+        self.targets_by_path.insert(path_prefix, target_id);
+        self.assign_packages();
+
+        target_id
     }
 
     /// Add a type declaration to the index
@@ -156,70 +829,620 @@ impl Index {
         kind: Kind,
         file: &str,
         point: Point,
-        references: &[(&str, &Point)],
+        references: &[(&str, &Point, ReferenceKind, Confidence)],
     ) -> TypeId {
-        let file_id = *self.file_ids.entry(file.to_string()).or_insert_with(|| {
-            self.files.push(file.to_string());
+        let file_id = self.add_file(file);
 
-            self.files.len() - 1
-        });
+        let mut dependencies = vec![];
+        let mut unresolved = vec![];
 
-        let dependencies: Vec<_> = references
-            .iter()
-            .map(|(type_name, &ref_point)| {
-                let type_id = self.add_reference(type_name);
+        for &(type_name, &ref_point, kind, confidence) in references {
+            match confidence {
+                Confidence::High => {
+                    let type_id = self.add_resolved(type_name, Some(name), file_id);
 
-                (type_id, ref_point)
-            })
-            .collect();
+                    dependencies.push((type_id, ref_point, kind, confidence));
+                }
+                // Names in expressions are only references if they are declared types
+                Confidence::Medium => {
+                    match resolve::resolve_type(self, type_name, Some(name), file_id) {
+                        Some(type_id) => dependencies.push((type_id, ref_point, kind, confidence)),
+                        None => unresolved.push((type_name.to_string(), ref_point, kind)),
+                    }
+                }
+            }
+        }
 
         let declaration = Declaration {
             kind,
             point,
             file: file_id,
             dependencies,
+            unresolved,
+        };
+
+        // Create or update the type declaration, extensions extend a type visible in the file
+
+        let type_id = if kind == Kind::Extension {
+            self.add_resolved(name, None, file_id)
+        } else {
+            let module = self.module(file_id).map(str::to_string);
+
+            self.add_type(module.as_deref(), name)
         };
+        let declarations = &mut self.types[type_id].declarations;
+
+        declarations.push(declaration);
+        let declaration_index = declarations.len() - 1;
+
+        self.add_dependents(type_id, declaration_index);
+
+        type_id
+    }
+
+    /// Resolve references again once all declarations are known, and move extensions to
+    /// the types they extend. Each name resolves to a type nested in one of the enclosing
+    /// types of the referencing declaration first, innermost first, e.g. `Config` in
+    /// `Client` to `Client.Config`, then to a type in the current module and finally to
+    /// one in a module imported by the file.
+    pub fn resolve_references(&mut self) {
+        resolve::resolve_references(self);
+    }
+
+    /// Record the state of a scanned file
+    pub fn set_file_stamp(&mut self, file: &str, stamp: FileStamp) {
+        let file_id = self.add_file(file);
+
+        self.file_stamps.insert(file_id, stamp);
+    }
+
+    /// Record the hash of the queries the scanned files were parsed with
+    pub fn set_queries_hash(&mut self, hash: u64) {
+        self.queries_hash = Some(hash);
+    }
+
+    /// Record the module a file is by itself, if any, before adding its declarations
+    pub fn set_file_module(&mut self, file: &str, module: Option<&str>) {
+        let file_id = self.add_file(file);
+
+        match module {
+            Some(module) => self.file_modules.insert(file_id, module.to_string()),
+            None => self.file_modules.remove(&file_id),
+        };
+    }
+
+    /// Record the modules a file imports, before adding its declarations
+    pub fn set_file_imports(&mut self, file: &str, imports: &[&str]) {
+        let file_id = self.add_file(file);
+
+        if is_bridging_header(file) && !self.bridging_headers.contains(&file_id) {
+            self.bridging_headers.push(file_id);
+        }
+
+        self.file_imports
+            .insert(file_id, imports.iter().map(|i| i.to_string()).collect());
+    }
+
+    /// Record the syntax errors found when parsing a file
+    pub fn set_file_errors(&mut self, file: &str, errors: Vec<SyntaxError>) {
+        let file_id = self.add_file(file);
+
+        if errors.is_empty() {
+            self.file_errors.remove(&file_id);
+        } else {
+            self.file_errors.insert(file_id, errors);
+        }
+    }
+
+    /// Remove all declarations made in the files, e.g. before scanning them again
+    /// Types which are no longer declared anywhere become external.
+    pub fn remove_files(&mut self, files: &[&str]) {
+        let file_ids: HashSet<FileId> = files
+            .iter()
+            .filter_map(|file| self.file_ids.get(*file).copied())
+            .collect();
+
+        if file_ids.is_empty() {
+            return;
+        }
+
+        for t in &mut self.types {
+            t.declarations
+                .retain(|declaration| !file_ids.contains(&declaration.file));
+        }
+
+        for file_id in &file_ids {
+            self.file_stamps.remove(file_id);
+            self.file_modules.remove(file_id);
+            self.file_imports.remove(file_id);
+            self.file_errors.remove(file_id);
+        }
+
+        self.bridging_headers
+            .retain(|file_id| !file_ids.contains(file_id));
+
+        // Declaration indexes have shifted
+        self.rebuild_dependents();
+    }
+
+    fn add_file(&mut self, file: &str) -> FileId {
+        if let Some(&file_id) = self.file_ids.get(file) {
+            return file_id;
+        }
+
+        self.files.push(file.to_string());
+        let file_id = self.files.len() - 1;
+
+        self.file_ids.insert(file.to_string(), file_id);
+        self.assign_package(file_id);
 
-        // Create or update the type declaration
+        file_id
+    }
+
+    fn assign_packages(&mut self) {
+        for file_id in 0..self.files.len() {
+            self.assign_package(file_id);
+        }
+    }
+
+    // Assigns a file to a package and one of the package's targets
+    fn assign_package(&mut self, file_id: FileId) {
+        let path = &self.files[file_id];
 
-        match self.type_ids.get(name) {
-            Some(&type_id) => {
-                self.types[type_id].declarations.push(declaration);
+        let Some((_, &package_id)) = self.packages_by_path.get_longest_common_prefix(path) else {
+            self.file_packages.remove(&file_id);
+            self.file_targets.remove(&file_id);
 
-                type_id
+            return;
+        };
+
+        self.file_packages.insert(file_id, package_id);
+
+        // Targets of a package nested in another directory of the package don't count
+        match self.targets_by_path.get_longest_common_prefix(path) {
+            Some((_, &target_id)) if target_id.0 == package_id => {
+                self.file_targets.insert(file_id, target_id)
             }
-            None => {
-                let t = Type {
-                    name: name.to_string(),
-                    declarations: vec![declaration],
-                };
+            _ => self.file_targets.remove(&file_id),
+        };
+    }
 
-                self.types.push(t);
+    fn rebuild_dependents(&mut self) {
+        self.dependents.clear();
 
-                let type_id = self.types.len() - 1;
-                self.type_ids.insert(name.to_string(), type_id);
+        for type_id in 0..self.types.len() {
+            for declaration_index in 0..self.types[type_id].declarations.len() {
+                self.add_dependents(type_id, declaration_index);
+            }
+        }
+    }
 
-                type_id
+    // Record the reverse edges of a declaration, once per referenced type
+    fn add_dependents(&mut self, type_id: TypeId, declaration_index: usize) {
+        for (dependency_id, _, _, _) in
+            &self.types[type_id].declarations[declaration_index].dependencies
+        {
+            let edges = self.dependents.entry(*dependency_id).or_default();
+
+            if !edges.contains(&(type_id, declaration_index)) {
+                edges.push((type_id, declaration_index));
             }
         }
     }
 
-    pub fn add_reference(&mut self, name: &str) -> TypeId {
-        match self.type_ids.get(name) {
-            Some(&type_id) => type_id,
-            None => {
-                let t = Type {
-                    name: name.to_string(),
-                    declarations: vec![],
-                };
+    // The type a name referenced from a file resolves to with the declarations known so
+    // far, or the type of the name outside of any module
+    fn add_resolved(&mut self, name: &str, scope: Option<&str>, file: FileId) -> TypeId {
+        match resolve::resolve(self, name, scope, file) {
+            resolve::Resolution::Resolved(type_id) => type_id,
+            _ => self.add_type(None, name),
+        }
+    }
 
-                self.types.push(t);
+    fn add_type(&mut self, module: Option<&str>, name: &str) -> TypeId {
+        let existing = self.type_ids.get(name).and_then(|type_ids| {
+            type_ids
+                .iter()
+                .copied()
+                .find(|&type_id| self.types[type_id].module.as_deref() == module)
+        });
 
-                let type_id = self.types.len() - 1;
-                self.type_ids.insert(name.to_string(), type_id);
+        if let Some(type_id) = existing {
+            return type_id;
+        }
 
-                type_id
-            }
+        self.types.push(Type {
+            name: name.to_string(),
+            module: module.map(str::to_string),
+            declarations: vec![],
+        });
+
+        let type_id = self.types.len() - 1;
+        self.type_ids
+            .entry(name.to_string())
+            .or_default()
+            .push(type_id);
+
+        type_id
+    }
+}
+
+// Objective-C header exposing the headers it imports to Swift, e.g.
+// App/App-Bridging-Header.h
+fn is_bridging_header(file: &str) -> bool {
+    file.ends_with("-Bridging-Header.h")
+}
+
+// Make sure "Core" doesn't match files in "CoreUtils"
+fn directory_prefix(path: &str) -> String {
+    if path.ends_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::parser::{CustomQueries, Parser, Swift};
+
+    #[test]
+    fn removes_declarations_made_in_a_file() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[(
+                "OtherType",
+                &Point::new(11, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Class,
+            "./OtherType.swift",
+            Point::new(1, 6),
+            &[(
+                "MyType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "MyType",
+            Kind::Extension,
+            "./MyType+Other.swift",
+            Point::new(1, 10),
+            &[(
+                "OtherType",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.set_file_stamp(
+            "./OtherType.swift",
+            FileStamp {
+                modified: 1,
+                size: 2,
+                hash: 3,
+            },
+        );
+
+        index.remove_files(&["./OtherType.swift", "./MyType.swift"]);
+
+        let my_type = index.type_id("MyType").unwrap();
+        let other_type = index.type_id("OtherType").unwrap();
+
+        assert_eq!(
+            index.get_type(other_type).unwrap().origin(),
+            TypeOrigin::External
+        );
+        assert_eq!(index.get_type(my_type).unwrap().declarations.len(), 1);
+        assert_eq!(index.dependents_of(other_type), &[(my_type, 0)]);
+        assert!(index.dependents_of(my_type).is_empty());
+        assert!(index.file_stamps().is_empty());
+    }
+
+    #[test]
+    fn classifies_inheritance_by_the_kind_of_the_inherited_type() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyView",
+            Kind::Class,
+            "./MyView.swift",
+            Point::new(1, 6),
+            &[
+                (
+                    "Delegate",
+                    &Point::new(1, 15),
+                    ReferenceKind::Inheritance,
+                    Confidence::High,
+                ),
+                (
+                    "Base",
+                    &Point::new(1, 25),
+                    ReferenceKind::Conformance,
+                    Confidence::High,
+                ),
+                (
+                    "Unknown",
+                    &Point::new(1, 31),
+                    ReferenceKind::Conformance,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "Delegate",
+            Kind::Protocol,
+            "./Delegate.swift",
+            Point::new(1, 9),
+            &[],
+        );
+        index.add_declaration("Base", Kind::Class, "./Base.swift", Point::new(1, 6), &[]);
+
+        let my_view = index.get_type(index.type_id("MyView").unwrap()).unwrap();
+        let kinds = |name| {
+            let kinds =
+                index.reference_kinds(&my_view.declarations[0], index.type_id(name).unwrap());
+
+            kinds.into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(kinds("Delegate"), vec![ReferenceKind::Conformance]);
+        assert_eq!(kinds("Base"), vec![ReferenceKind::Inheritance]);
+        assert_eq!(kinds("Unknown"), vec![ReferenceKind::Conformance]);
+    }
+
+    #[test]
+    fn resolves_nested_types_from_their_parent_scope() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "Config",
+            Kind::Struct,
+            "./Config.swift",
+            Point::new(1, 7),
+            &[],
+        );
+        index.add_declaration(
+            "Client",
+            Kind::Struct,
+            "./Client.swift",
+            Point::new(1, 7),
+            &[(
+                "Config",
+                &Point::new(2, 10),
+                ReferenceKind::StoredProperty,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "Client.Session",
+            Kind::Class,
+            "./Client.swift",
+            Point::new(3, 10),
+            &[(
+                "Config",
+                &Point::new(4, 14),
+                ReferenceKind::StoredProperty,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "Client.Config",
+            Kind::Struct,
+            "./Client.swift",
+            Point::new(6, 11),
+            &[],
+        );
+        index.add_declaration(
+            "App",
+            Kind::Struct,
+            "./App.swift",
+            Point::new(1, 7),
+            &[(
+                "Config",
+                &Point::new(2, 10),
+                ReferenceKind::StoredProperty,
+                Confidence::High,
+            )],
+        );
+
+        index.resolve_references();
+
+        let dependencies = |name| {
+            let t = index.get_type(index.type_id(name).unwrap()).unwrap();
+
+            t.declarations[0]
+                .dependencies()
+                .into_keys()
+                .map(|id| index.get_type(id).unwrap().name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(dependencies("Client"), vec!["Client.Config"]);
+        assert_eq!(dependencies("Client.Session"), vec!["Client.Config"]);
+        assert_eq!(dependencies("App"), vec!["Config"]);
+    }
+
+    #[test]
+    fn finds_nested_types_by_unqualified_name() {
+        let mut index = Index::new();
+        let mut declare = |name| {
+            index.add_declaration(name, Kind::Struct, "./Types.swift", Point::new(1, 7), &[]);
+        };
+        declare("Client.Session");
+        declare("Client.Config");
+        declare("Server.Config");
+
+        assert_eq!(
+            index.existing_type_id("Session").unwrap(),
+            index.type_id("Client.Session").unwrap()
+        );
+        assert_eq!(
+            index.existing_type_id("Server.Config").unwrap(),
+            index.type_id("Server.Config").unwrap()
+        );
+        assert_eq!(
+            index.existing_type_id("Config").unwrap_err().to_string(),
+            "Type name Config is ambiguous, use one of Client.Config, Server.Config."
+        );
+    }
+
+    #[test]
+    fn assigns_files_to_the_innermost_package() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "Early",
+            Kind::Struct,
+            "./Packages/Core/Sources/Early.swift",
+            Point::new(1, 7),
+            &[],
+        );
+        index.add_package("Core", "./Packages/Core");
+        index.add_package("CoreTests", "./Packages/Core/Tests");
+        index.add_package("Cored", "./Packages/Cored");
+        index.add_declaration(
+            "Late",
+            Kind::Struct,
+            "./Packages/Core/Tests/Late.swift",
+            Point::new(1, 7),
+            &[],
+        );
+        index.add_declaration("App", Kind::Class, "./App/App.swift", Point::new(1, 6), &[]);
+
+        let package = |name: &str| {
+            let t = index.get_type(index.type_id(name).unwrap()).unwrap();
+
+            index.package_name(&t.declarations[0])
+        };
+
+        assert_eq!(package("Early"), Some("Core"));
+        assert_eq!(package("Late"), Some("CoreTests"));
+        assert_eq!(package("App"), None);
+        assert_eq!(index.package_id_by_name("Cored"), Some(2));
+    }
+
+    #[test]
+    fn assigns_files_to_targets() {
+        let mut index = Index::new();
+        let core = index.add_package("Core", "./Packages/Core");
+        index.add_target(core, "Core", TargetKind::Regular, None, &[]);
+        index.add_target(
+            core,
+            "CoreTests",
+            TargetKind::Test,
+            None,
+            &[("Core", None), ("Testing", Some("swift-testing"))],
+        );
+        index.add_target(
+            core,
+            "Extras",
+            TargetKind::Regular,
+            Some("./Extras/Sources"),
+            &[],
+        );
+
+        let declare = |index: &mut Index, name: &str, file: &str| {
+            index.add_declaration(name, Kind::Struct, file, Point::new(1, 7), &[]);
+        };
+        declare(
+            &mut index,
+            "Model",
+            "./Packages/Core/Sources/Core/Model.swift",
+        );
+        declare(
+            &mut index,
+            "ModelTests",
+            "./Packages/Core/Tests/CoreTests/ModelTests.swift",
+        );
+        declare(
+            &mut index,
+            "Extra",
+            "./Packages/Core/Extras/Sources/Extra.swift",
+        );
+        declare(&mut index, "Loose", "./Packages/Core/Loose.swift");
+
+        fn target<'a>(index: &'a Index, name: &str) -> Option<&'a str> {
+            let t = index.get_type(index.type_id(name).unwrap()).unwrap();
+
+            index.target_name(&t.declarations[0])
+        }
+
+        assert_eq!(target(&index, "Model"), Some("Core"));
+        assert_eq!(target(&index, "ModelTests"), Some("CoreTests"));
+        assert_eq!(target(&index, "Extra"), Some("Extras"));
+        assert_eq!(target(&index, "Loose"), None);
+
+        // Scanning the manifest again starts over
+        index.add_package("Core", "./Packages/Core");
+
+        assert_eq!(target(&index, "Model"), None);
+        assert!(index.get_package(core).unwrap().targets.is_empty());
+    }
+
+    #[test]
+    fn reports_syntax_errors_by_file() {
+        let parser = Parser::new(&Swift, &CustomQueries::default()).unwrap();
+        let sources = [
+            ("./Money.swift", "struct Money {\n    let amount: Int\n}\n"),
+            (
+                "./Order.swift",
+                "struct Order {\n    let total: Money\n\nfunc place() {}\n",
+            ),
+            ("./Total.swift", "let total = (1 +\nlet tax = 2\n"),
+        ];
+
+        let mut index = Index::new();
+
+        for (path, source) in sources {
+            let tree = parser.parse(source.to_string()).unwrap();
+
+            let errors = tree
+                .syntax_errors()
+                .into_iter()
+                .map(|error| SyntaxError {
+                    start: error.start,
+                    end: error.end,
+                    missing: error.missing,
+                })
+                .collect();
+
+            index.set_file_errors(path, errors);
         }
+
+        let errors: Vec<_> = index
+            .syntax_errors()
+            .into_iter()
+            .flat_map(|(path, errors)| {
+                errors.iter().map(move |error| {
+                    (
+                        path,
+                        error.start.row + 1,
+                        error.start.column + 1,
+                        error.missing.as_deref(),
+                    )
+                })
+            })
+            .collect();
+
+        // The clean file reports no errors
+        assert_eq!(
+            errors,
+            vec![
+                ("./Order.swift", 5, 1, Some("}")),
+                ("./Total.swift", 2, 5, None),
+                ("./Total.swift", 2, 12, Some(")")),
+            ]
+        );
     }
 }
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use tree_sitter::Point;
+
+use super::{Index, PackageId, TypeId};
+
+// Number of example references kept for each edge between packages
+const EXAMPLE_LIMIT: usize = 3;
+
+/// Aggregated references from types in one package to types in another
+#[derive(Debug, PartialEq)]
+pub struct PackageEdge {
+    pub from: PackageId,
+    pub to: PackageId,
+    /// Number of references
+    pub count: usize,
+    /// The first few references, in index order
+    pub examples: Vec<PackageReference>,
+}
+
+/// A single reference creating an edge between packages
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PackageReference {
+    pub from: TypeId,
+    /// Index of the referencing declaration of the `from` type
+    pub declaration: usize,
+    pub to: TypeId,
+    pub point: Point,
+}
+
+/// Collapse the type graph into a graph of packages. References within a package, from
+/// files outside of any package and to types not declared in a package are left out.
+/// Edges are sorted by the names of the packages.
+pub fn package_graph(index: &Index) -> Vec<PackageEdge> {
+    let type_packages: Vec<Option<PackageId>> = index
+        .types
+        .iter()
+        // Extensions don't move a type into another package
+        .map(|t| index.package_id(t.primary_declaration()?))
+        .collect();
+
+    let mut edges: BTreeMap<(PackageId, PackageId), PackageEdge> = BTreeMap::new();
+
+    for (type_id, t) in index.types.iter().enumerate() {
+        for (idx, declaration) in t.declarations.iter().enumerate() {
+            let Some(from) = index.package_id(declaration) else {
+                continue;
+            };
+
+            for &(dependency, point, _, _) in &declaration.dependencies {
+                let Some(to) = type_packages[dependency] else {
+                    continue;
+                };
+
+                if from == to {
+                    continue;
+                }
+
+                let edge = edges.entry((from, to)).or_insert_with(|| PackageEdge {
+                    from,
+                    to,
+                    count: 0,
+                    examples: vec![],
+                });
+
+                edge.count += 1;
+
+                if edge.examples.len() < EXAMPLE_LIMIT {
+                    edge.examples.push(PackageReference {
+                        from: type_id,
+                        declaration: idx,
+                        to: dependency,
+                        point,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut edges: Vec<_> = edges.into_values().collect();
+    edges.sort_by_key(|edge| {
+        (
+            &index.packages[edge.from].name,
+            &index.packages[edge.to].name,
+        )
+    });
+
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::index::{Confidence, Index, Kind, ReferenceKind};
+
+    fn add(index: &mut Index, name: &str, file: &str, references: &[&str]) {
+        let point = Point::new(1, 1);
+        let references: Vec<_> = references
+            .iter()
+            .map(|r| (*r, &point, ReferenceKind::BodyCall, Confidence::High))
+            .collect();
+
+        index.add_declaration(name, Kind::Struct, file, point, &references);
+    }
+
+    #[test]
+    fn aggregates_references_between_packages() {
+        let mut index = Index::new();
+        index.add_package("App", "./App");
+        index.add_package("Core", "./Core");
+        index.add_package("UI", "./UI");
+
+        add(
+            &mut index,
+            "AppView",
+            "./App/AppView.swift",
+            &["Button", "Model", "Model"],
+        );
+        add(
+            &mut index,
+            "AppModel",
+            "./App/AppModel.swift",
+            &["Model", "AppView"],
+        );
+        add(
+            &mut index,
+            "Button",
+            "./UI/Button.swift",
+            &["Model", "Color"],
+        );
+        add(&mut index, "Model", "./Core/Model.swift", &[]);
+        add(&mut index, "Script", "./Scripts/Script.swift", &["Model"]);
+
+        let package = |name| index.package_id_by_name(name).unwrap();
+        let actual: Vec<_> = index
+            .package_graph()
+            .into_iter()
+            .map(|edge| (edge.from, edge.to, edge.count, edge.examples.len()))
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                (package("App"), package("Core"), 3, 3),
+                (package("App"), package("UI"), 1, 1),
+                (package("UI"), package("Core"), 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn attributes_extended_types_to_their_declaring_package() {
+        let mut index = Index::new();
+        index.add_package("Core", "./Core");
+        index.add_package("UI", "./UI");
+
+        add(&mut index, "Model", "./Core/Model.swift", &[]);
+        index.add_declaration(
+            "Model",
+            Kind::Extension,
+            "./UI/Model+View.swift",
+            Point::new(1, 10),
+            &[(
+                "Model",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        let actual = index.package_graph();
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].from, index.package_id_by_name("UI").unwrap());
+        assert_eq!(actual[0].to, index.package_id_by_name("Core").unwrap());
+        assert_eq!(
+            actual[0].examples,
+            vec![PackageReference {
+                from: index.type_id("Model").unwrap(),
+                declaration: 1,
+                to: index.type_id("Model").unwrap(),
+                point: Point::new(2, 10),
+            }]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{Index, TypeId};
+
+/// Breadth first search for the shortest path between two types
+pub fn shortest_path(index: &Index, from: TypeId, to: TypeId) -> Option<Vec<TypeId>> {
+    // Type each type was first reached from
+    let mut parents: Vec<Option<TypeId>> = vec![None; index.types.len()];
+    let mut queue = VecDeque::from([from]);
+
+    parents[from] = Some(from);
+
+    while let Some(type_id) = queue.pop_front() {
+        if type_id == to {
+            let mut path = vec![to];
+            let mut current = to;
+
+            while current != from {
+                current = parents[current].expect("visited type has no parent");
+                path.push(current);
+            }
+
+            path.reverse();
+
+            return Some(path);
+        }
+
+        for successor in index.successors(type_id) {
+            if parents[successor].is_none() {
+                parents[successor] = Some(type_id);
+                queue.push_back(successor);
+            }
+        }
+    }
+
+    None
+}
+
+/// Breadth first search for up to `limit` paths between two types which don't visit
+/// any type more than once, shortest paths first
+pub fn simple_paths(index: &Index, from: TypeId, to: TypeId, limit: usize) -> Vec<Vec<TypeId>> {
+    // Only extend paths with types which lead to the target
+    let reaching = types_reaching(index, to);
+
+    let mut paths = vec![];
+    let mut queue = VecDeque::from([vec![from]]);
+
+    while let Some(path) = queue.pop_front() {
+        if paths.len() >= limit {
+            break;
+        }
+
+        let last = *path.last().expect("paths are never empty");
+
+        for successor in index.successors(last) {
+            if path.contains(&successor) || !reaching.contains(&successor) {
+                continue;
+            }
+
+            let mut next = path.clone();
+            next.push(successor);
+
+            if successor == to {
+                paths.push(next);
+            } else {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    paths.truncate(limit);
+
+    paths
+}
+
+// Types from which a type can be reached, including the type itself
+fn types_reaching(index: &Index, to: TypeId) -> HashSet<TypeId> {
+    let mut reaching = HashSet::from([to]);
+    let mut queue = VecDeque::from([to]);
+
+    while let Some(type_id) = queue.pop_front() {
+        for &(dependent, _) in index.dependents_of(type_id) {
+            if reaching.insert(dependent) {
+                queue.push_back(dependent);
+            }
+        }
+    }
+
+    reaching
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Point;
+
+    use crate::index::{Confidence, Index, Kind, ReferenceKind};
+
+    fn add(index: &mut Index, name: &str, references: &[&str]) {
+        let point = Point::new(1, 1);
+        let references: Vec<_> = references
+            .iter()
+            .map(|r| (*r, &point, ReferenceKind::BodyCall, Confidence::High))
+            .collect();
+
+        index.add_declaration(name, Kind::Struct, "./File.swift", point, &references);
+    }
+
+    fn names(index: &Index, path: Vec<usize>) -> Vec<&str> {
+        path.into_iter()
+            .map(|id| index.get_type(id).unwrap().name.as_ref())
+            .collect()
+    }
+
+    fn graph() -> Index {
+        let mut index = Index::new();
+        add(&mut index, "A", &["B", "C"]);
+        add(&mut index, "B", &["D"]);
+        add(&mut index, "C", &["A", "E"]);
+        add(&mut index, "D", &["E", "External"]);
+
+        index
+    }
+
+    #[test]
+    fn finds_the_shortest_path() {
+        let index = graph();
+
+        let actual = index.shortest_path("A", "E").unwrap().unwrap();
+
+        assert_eq!(names(&index, actual), vec!["A", "C", "E"]);
+    }
+
+    #[test]
+    fn finds_no_path_against_the_edges() {
+        let index = graph();
+
+        assert_eq!(index.shortest_path("E", "A").unwrap(), None);
+    }
+
+    #[test]
+    fn finds_all_simple_paths() {
+        let index = graph();
+
+        let actual: Vec<_> = index
+            .simple_paths("A", "E", 10)
+            .unwrap()
+            .into_iter()
+            .map(|path| names(&index, path))
+            .collect();
+
+        assert_eq!(actual, vec![vec!["A", "C", "E"], vec!["A", "B", "D", "E"]]);
+
+        let shortest: Vec<_> = index
+            .simple_paths("A", "E", 1)
+            .unwrap()
+            .into_iter()
+            .map(|path| names(&index, path))
+            .collect();
+
+        assert_eq!(shortest, vec![vec!["A", "C", "E"]]);
+    }
+
+    #[test]
+    fn rejects_a_path_to_the_same_type() {
+        let index = graph();
+
+        let error = index.shortest_path("A", "A").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "A and A are the same type, use `drake cycles` to find dependencies leading back to it."
+        );
+        assert!(index.simple_paths("C", "C", 10).is_err());
+    }
+}
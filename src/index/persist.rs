@@ -0,0 +1,479 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use tree_sitter::Point;
+
+use super::{
+    is_bridging_header, Confidence, Declaration, FileId, FileStamp, Index, Kind, ReferenceKind,
+    SyntaxError, TargetKind, Type, TypeId,
+};
+
+// Bump on any change to the stored format, older index files are then rejected
+const FORMAT_VERSION: u32 = 11;
+
+// On-disk representation of the index. Only the storage is saved,
+// lookup maps are rebuilt on load.
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    // Hash of the queries the files were parsed with
+    queries_hash: Option<u64>,
+    packages: Vec<PackageEntry>,
+    files: Vec<FileEntry>,
+    types: Vec<TypeEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    path: String,
+    // Missing for files which are no longer scanned
+    stamp: Option<FileStamp>,
+    module: Option<String>,
+    imports: Vec<String>,
+    errors: Vec<SyntaxErrorEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyntaxErrorEntry {
+    // Row and column of the start and the end
+    start: (usize, usize),
+    end: (usize, usize),
+    missing: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackageEntry {
+    name: String,
+    path_prefix: String,
+    products: Vec<ProductEntry>,
+    dependencies: Vec<PackageDependencyEntry>,
+    targets: Vec<TargetEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProductEntry {
+    name: String,
+    targets: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackageDependencyEntry {
+    name: String,
+    path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TargetEntry {
+    name: String,
+    kind: TargetKind,
+    // Relative to the package, targets are added again on load
+    path: String,
+    // Target or product name and the package vending the product
+    dependencies: Vec<(String, Option<String>)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TypeEntry {
+    name: String,
+    module: Option<String>,
+    declarations: Vec<DeclarationEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeclarationEntry {
+    kind: Kind,
+    row: usize,
+    column: usize,
+    file: FileId,
+    // Referenced type, row and column of the reference, its kind and confidence
+    dependencies: Vec<(TypeId, usize, usize, ReferenceKind, Confidence)>,
+    // Name used in an expression, row and column and kind of the use
+    unresolved: Vec<(String, usize, usize, ReferenceKind)>,
+}
+
+impl Index {
+    /// Save the index to a file
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Could not create index file {}", path.display()))?;
+
+        let mut writer = BufWriter::new(file);
+        self.write(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Load an index previously saved with `save`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open index file {}", path.display()))?;
+
+        Self::read(BufReader::new(file))
+            .with_context(|| format!("Could not load index file {}", path.display()))
+    }
+
+    fn write(&self, writer: impl Write) -> anyhow::Result<()> {
+        let packages = self
+            .packages
+            .iter()
+            .map(|package| PackageEntry {
+                name: package.name.clone(),
+                path_prefix: package.path_prefix.clone(),
+                products: package
+                    .products
+                    .iter()
+                    .map(|product| ProductEntry {
+                        name: product.name.clone(),
+                        targets: product.targets.clone(),
+                    })
+                    .collect(),
+                dependencies: package
+                    .dependencies
+                    .iter()
+                    .map(|dependency| PackageDependencyEntry {
+                        name: dependency.name.clone(),
+                        path: dependency.path.clone(),
+                    })
+                    .collect(),
+                targets: package
+                    .targets
+                    .iter()
+                    .map(|target| TargetEntry {
+                        name: target.name.clone(),
+                        kind: target.kind,
+                        path: target.path_prefix[package.path_prefix.len()..].to_string(),
+                        dependencies: target
+                            .dependencies
+                            .iter()
+                            .map(|dependency| (dependency.name.clone(), dependency.package.clone()))
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let files = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(file_id, path)| FileEntry {
+                path: path.clone(),
+                stamp: self.file_stamps.get(&file_id).copied(),
+                module: self.file_modules.get(&file_id).cloned(),
+                imports: self.file_imports.get(&file_id).cloned().unwrap_or_default(),
+                errors: self
+                    .file_errors
+                    .get(&file_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|error| SyntaxErrorEntry {
+                        start: (error.start.row, error.start.column),
+                        end: (error.end.row, error.end.column),
+                        missing: error.missing.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let types = self
+            .types
+            .iter()
+            .map(|t| TypeEntry {
+                name: t.name.clone(),
+                module: t.module.clone(),
+                declarations: t
+                    .declarations
+                    .iter()
+                    .map(|declaration| DeclarationEntry {
+                        kind: declaration.kind,
+                        row: declaration.point.row,
+                        column: declaration.point.column,
+                        file: declaration.file,
+                        dependencies: declaration
+                            .dependencies
+                            .iter()
+                            .map(|&(type_id, point, kind, confidence)| {
+                                (type_id, point.row, point.column, kind, confidence)
+                            })
+                            .collect(),
+                        unresolved: declaration
+                            .unresolved
+                            .iter()
+                            .map(|(name, point, kind)| {
+                                (name.clone(), point.row, point.column, *kind)
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let index_file = IndexFile {
+            version: FORMAT_VERSION,
+            queries_hash: self.queries_hash,
+            packages,
+            files,
+            types,
+        };
+
+        serde_json::to_writer(writer, &index_file)?;
+
+        Ok(())
+    }
+
+    fn read(reader: impl Read) -> anyhow::Result<Self> {
+        let index_file: IndexFile = serde_json::from_reader(reader)?;
+
+        if index_file.version != FORMAT_VERSION {
+            bail!(
+                "Index format version {} is not supported (expected {}), please scan again",
+                index_file.version,
+                FORMAT_VERSION
+            );
+        }
+
+        let mut index = Index::new();
+        index.queries_hash = index_file.queries_hash;
+
+        for package in index_file.packages {
+            let package_id = index.add_package(&package.name, &package.path_prefix);
+
+            for product in &package.products {
+                let targets: Vec<_> = product.targets.iter().map(String::as_str).collect();
+
+                index.add_product(package_id, &product.name, &targets);
+            }
+
+            for dependency in &package.dependencies {
+                index.add_package_dependency(
+                    package_id,
+                    &dependency.name,
+                    dependency.path.as_deref(),
+                );
+            }
+
+            for target in &package.targets {
+                let dependencies: Vec<_> = target
+                    .dependencies
+                    .iter()
+                    .map(|(name, package)| (name.as_str(), package.as_deref()))
+                    .collect();
+
+                index.add_target(
+                    package_id,
+                    &target.name,
+                    target.kind,
+                    Some(&target.path),
+                    &dependencies,
+                );
+            }
+        }
+
+        for (file_id, file) in index_file.files.into_iter().enumerate() {
+            index.file_ids.insert(file.path.clone(), file_id);
+            index.files.push(file.path);
+
+            if let Some(stamp) = file.stamp {
+                index.file_stamps.insert(file_id, stamp);
+            }
+
+            if let Some(module) = file.module {
+                index.file_modules.insert(file_id, module);
+            }
+
+            if is_bridging_header(&index.files[file_id]) {
+                index.bridging_headers.push(file_id);
+            }
+
+            if !file.imports.is_empty() {
+                index.file_imports.insert(file_id, file.imports);
+            }
+
+            if !file.errors.is_empty() {
+                let errors = file
+                    .errors
+                    .into_iter()
+                    .map(|error| SyntaxError {
+                        start: Point::new(error.start.0, error.start.1),
+                        end: Point::new(error.end.0, error.end.1),
+                        missing: error.missing,
+                    })
+                    .collect();
+
+                index.file_errors.insert(file_id, errors);
+            }
+        }
+
+        let type_count = index_file.types.len();
+
+        for (type_id, t) in index_file.types.into_iter().enumerate() {
+            let mut declarations = vec![];
+
+            for declaration in t.declarations {
+                if declaration.file >= index.files.len() {
+                    bail!("Declaration of {} refers to an unknown file", t.name);
+                }
+
+                let mut dependencies = vec![];
+
+                for (dependency_id, row, column, kind, confidence) in declaration.dependencies {
+                    if dependency_id >= type_count {
+                        bail!("Declaration of {} refers to an unknown type", t.name);
+                    }
+
+                    dependencies.push((dependency_id, Point::new(row, column), kind, confidence));
+                }
+
+                declarations.push(Declaration {
+                    kind: declaration.kind,
+                    point: Point::new(declaration.row, declaration.column),
+                    file: declaration.file,
+                    dependencies,
+                    unresolved: declaration
+                        .unresolved
+                        .into_iter()
+                        .map(|(name, row, column, kind)| (name, Point::new(row, column), kind))
+                        .collect(),
+                });
+            }
+
+            index
+                .type_ids
+                .entry(t.name.clone())
+                .or_default()
+                .push(type_id);
+            index.types.push(Type {
+                name: t.name,
+                module: t.module,
+                declarations,
+            });
+        }
+
+        index.assign_packages();
+        index.rebuild_dependents();
+
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn loads_a_saved_index() {
+        let mut index = Index::new();
+        let core = index.add_package("Core", "./Packages/Core");
+        index.add_product(core, "Core", &["Core"]);
+        index.add_package_dependency(core, "swift-log", None);
+        index.add_target(
+            core,
+            "Core",
+            TargetKind::Regular,
+            Some("."),
+            &[("Logging", Some("swift-log"))],
+        );
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./Packages/Core/MyType.swift",
+            Point::new(10, 20),
+            &[
+                (
+                    "OtherType",
+                    &Point::new(11, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "ExternalType",
+                    &Point::new(12, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Protocol,
+            "./OtherType.swift",
+            Point::new(1, 9),
+            &[],
+        );
+        let stamp = FileStamp {
+            modified: 1700000000000000000,
+            size: 1024,
+            hash: 42,
+        };
+        index.set_file_stamp("./OtherType.swift", stamp);
+        let errors = vec![SyntaxError {
+            start: Point::new(3, 0),
+            end: Point::new(3, 12),
+            missing: Some("}".to_string()),
+        }];
+        index.set_file_errors("./OtherType.swift", errors.clone());
+        index.set_file_module("./web/router.ts", Some("./web/router"));
+        index.set_queries_hash(42);
+
+        let mut buffer = vec![];
+        index.write(&mut buffer).unwrap();
+        let loaded = Index::read(buffer.as_slice()).unwrap();
+
+        let expected: Vec<_> = index.walk("MyType").unwrap().collect();
+        let actual: Vec<_> = loaded.walk("MyType").unwrap().collect();
+        assert_eq!(actual, expected);
+
+        let other_type = loaded.type_id("OtherType").unwrap();
+        assert_eq!(
+            loaded.dependents_of(other_type),
+            index.dependents_of(other_type)
+        );
+
+        let declaration = &loaded
+            .get_type(loaded.type_id("MyType").unwrap())
+            .unwrap()
+            .declarations[0];
+        assert_eq!(loaded.package_name(declaration), Some("Core"));
+        assert_eq!(loaded.target_name(declaration), Some("Core"));
+
+        let package = loaded.get_package(core).unwrap();
+        let expected = index.get_package(core).unwrap();
+        assert_eq!(package.products, expected.products);
+        assert_eq!(package.dependencies, expected.dependencies);
+        assert_eq!(package.targets, expected.targets);
+        assert_eq!(
+            loaded.file_path(declaration).as_deref(),
+            Some("./Packages/Core/MyType.swift")
+        );
+        assert_eq!(
+            loaded.file_stamps(),
+            HashMap::from([("./OtherType.swift".to_string(), stamp)])
+        );
+        assert_eq!(
+            loaded.syntax_errors(),
+            vec![("./OtherType.swift", errors.as_slice())]
+        );
+        assert_eq!(
+            loaded.module(loaded.file_ids["./web/router.ts"]),
+            Some("./web/router")
+        );
+        assert_eq!(loaded.queries_hash(), Some(42));
+    }
+
+    #[test]
+    fn rejects_other_format_versions() {
+        let json = r#"{"version":0,"packages":[],"files":[],"types":[]}"#;
+
+        assert!(Index::read(json.as_bytes()).is_err());
+    }
+}
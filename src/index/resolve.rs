@@ -0,0 +1,558 @@
+use tree_sitter::Point;
+
+use super::{directory_prefix, Confidence, FileId, Index, Kind, TypeId};
+
+/// A reference to a type name declared in more than one of the imported modules
+#[derive(Debug, PartialEq)]
+pub struct AmbiguousReference {
+    pub from: TypeId,
+    /// Index of the referencing declaration of the `from` type
+    pub declaration: usize,
+    pub point: Point,
+    /// The types the name could refer to, in index order
+    pub candidates: Vec<TypeId>,
+}
+
+// Outcome of looking up a type name as seen from a file
+#[derive(Debug, PartialEq)]
+pub(super) enum Resolution {
+    Resolved(TypeId),
+    Ambiguous(Vec<TypeId>),
+    Unresolved,
+}
+
+/// Look a type name up from a file, in the scope of the type the reference is made in.
+/// Nested types of the enclosing types are tried first, innermost first, then the name
+/// itself: in the module given, in the current module, in the imported modules and
+/// finally in the only module declaring it, e.g. for languages whose imports don't name
+/// modules. Modules imported by a bridging header count as imported by the files in its
+/// directory.
+pub(super) fn resolve(index: &Index, name: &str, scope: Option<&str>, file: FileId) -> Resolution {
+    let mut scope = scope;
+
+    while let Some(current) = scope {
+        match resolve_in_modules(index, &format!("{}.{}", current, name), file) {
+            Resolution::Unresolved => scope = current.rsplit_once('.').map(|(outer, _)| outer),
+            resolution => return resolution,
+        }
+    }
+
+    resolve_in_modules(index, name, file)
+}
+
+fn resolve_in_modules(index: &Index, name: &str, file: FileId) -> Resolution {
+    // Module qualified names, e.g. Core.Router
+    if let Some((module, rest)) = name.split_once('.') {
+        if let Some(type_id) = index
+            .is_module(module)
+            .then(|| declared(index, Some(module), rest))
+            .flatten()
+        {
+            return Resolution::Resolved(type_id);
+        }
+    }
+
+    let module = index.module(file);
+
+    // The current module shadows imported ones
+    if let Some(type_id) = declared(index, module, name) {
+        return Resolution::Resolved(type_id);
+    }
+
+    let imports = imports(index, file);
+    let declared: Vec<TypeId> = index
+        .type_ids
+        .get(name)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&type_id| is_declared(index, type_id))
+        .collect();
+    let imported: Vec<TypeId> = declared
+        .iter()
+        .copied()
+        .filter(|&type_id| {
+            index.types[type_id]
+                .module
+                .as_ref()
+                .is_some_and(|module| imports.contains(&module))
+        })
+        .collect();
+
+    match (imported.len(), declared.len()) {
+        (1, _) => Resolution::Resolved(imported[0]),
+        (0, 1) => Resolution::Resolved(declared[0]),
+        (0, _) => Resolution::Unresolved,
+        _ => Resolution::Ambiguous(imported),
+    }
+}
+
+// Modules imported by the file and by the bridging headers in its directory or above
+fn imports(index: &Index, file: FileId) -> Vec<&String> {
+    let path = &index.files[file];
+    let bridged = index.bridging_headers.iter().filter(|&&header| {
+        let (directory, _) = index.files[header].rsplit_once('/').unwrap_or_default();
+
+        path.starts_with(&directory_prefix(directory))
+    });
+
+    std::iter::once(&file)
+        .chain(bridged)
+        .filter_map(|file| index.file_imports.get(file))
+        .flatten()
+        .collect()
+}
+
+// A type of the name declared in the module, rather than only extended
+fn declared(index: &Index, module: Option<&str>, name: &str) -> Option<TypeId> {
+    index.type_ids.get(name)?.iter().copied().find(|&type_id| {
+        index.types[type_id].module.as_deref() == module && is_declared(index, type_id)
+    })
+}
+
+fn is_declared(index: &Index, type_id: TypeId) -> bool {
+    index.types[type_id]
+        .declarations
+        .iter()
+        .any(|declaration| declaration.kind != Kind::Extension)
+}
+
+/// Look a name used in an expression up, it only refers to a type if it resolves to
+/// a type declared in the codebase, rather than e.g. a global constant
+pub(super) fn resolve_type(
+    index: &Index,
+    name: &str,
+    scope: Option<&str>,
+    file: FileId,
+) -> Option<TypeId> {
+    let Resolution::Resolved(type_id) = resolve(index, name, scope, file) else {
+        return None;
+    };
+
+    index.types[type_id]
+        .primary_declaration()
+        .is_some_and(|declaration| declaration.kind.is_type())
+        .then_some(type_id)
+}
+
+/// Resolve all extensions and references again, now that all declarations are known.
+/// Extensions move to the type they extend. References in type positions which are
+/// ambiguous or can't be resolved point at the type of the name outside of any module.
+/// Names used in expressions become references when they resolve to a declared type
+/// and stop being references when they no longer do.
+pub fn resolve_references(index: &mut Index) {
+    let mut changed = move_extensions(index);
+
+    let mut retargeted = vec![];
+    let mut demoted = vec![];
+    let mut promoted = vec![];
+
+    for (type_id, t) in index.types.iter().enumerate() {
+        for (idx, declaration) in t.declarations.iter().enumerate() {
+            for (dependency_idx, &(dependency, _, _, confidence)) in
+                declaration.dependencies.iter().enumerate()
+            {
+                let name = &index.types[dependency].name;
+
+                if confidence == Confidence::Medium {
+                    match resolve_type(index, name, Some(&t.name), declaration.file) {
+                        Some(resolved) if resolved != dependency => retargeted.push((
+                            type_id,
+                            idx,
+                            dependency_idx,
+                            Some(resolved),
+                            name.clone(),
+                        )),
+                        Some(_) => (),
+                        None => demoted.push((type_id, idx, dependency_idx)),
+                    }
+
+                    continue;
+                }
+
+                let resolved = match resolve(index, name, Some(&t.name), declaration.file) {
+                    Resolution::Resolved(resolved) => Some(resolved),
+                    // References resolved while adding by their module qualified name
+                    Resolution::Ambiguous(candidates) if candidates.contains(&dependency) => {
+                        continue
+                    }
+                    _ => None,
+                };
+
+                if resolved != Some(dependency) {
+                    retargeted.push((type_id, idx, dependency_idx, resolved, name.clone()));
+                }
+            }
+
+            for (unresolved_idx, (name, _, _)) in declaration.unresolved.iter().enumerate() {
+                if let Some(resolved) = resolve_type(index, name, Some(&t.name), declaration.file) {
+                    promoted.push((type_id, idx, unresolved_idx, resolved));
+                }
+            }
+        }
+    }
+
+    for (type_id, idx, dependency_idx, resolved, name) in retargeted {
+        let resolved = resolved.unwrap_or_else(|| index.add_type(None, &name));
+
+        if resolved != index.types[type_id].declarations[idx].dependencies[dependency_idx].0 {
+            index.types[type_id].declarations[idx].dependencies[dependency_idx].0 = resolved;
+            changed = true;
+        }
+    }
+
+    // Promoted names are appended to the dependencies, so demote first and remove from
+    // the back, so that the indexes of the remaining moves stay valid
+    for (type_id, idx, dependency_idx) in demoted.into_iter().rev() {
+        let (dependency, point, kind, _) = index.types[type_id].declarations[idx]
+            .dependencies
+            .remove(dependency_idx);
+        let name = index.types[dependency].name.clone();
+
+        index.types[type_id].declarations[idx]
+            .unresolved
+            .push((name, point, kind));
+        changed = true;
+    }
+
+    for (type_id, idx, unresolved_idx, resolved) in promoted.into_iter().rev() {
+        let declaration = &mut index.types[type_id].declarations[idx];
+        let (_, point, kind) = declaration.unresolved.remove(unresolved_idx);
+
+        declaration
+            .dependencies
+            .push((resolved, point, kind, Confidence::Medium));
+        changed = true;
+    }
+
+    if changed {
+        index.rebuild_dependents();
+    }
+}
+
+// Moves extensions to the types they extend, returns whether any moved
+fn move_extensions(index: &mut Index) -> bool {
+    let mut moves = vec![];
+
+    for (type_id, t) in index.types.iter().enumerate() {
+        for (idx, declaration) in t.declarations.iter().enumerate() {
+            if declaration.kind != Kind::Extension {
+                continue;
+            }
+
+            let extended = match resolve(index, &t.name, None, declaration.file) {
+                Resolution::Resolved(extended) => Some(extended),
+                Resolution::Ambiguous(candidates) if candidates.contains(&type_id) => continue,
+                _ => None,
+            };
+
+            if extended != Some(type_id) {
+                moves.push((type_id, idx, extended));
+            }
+        }
+    }
+
+    let mut moved = false;
+
+    // Remove from the back, so that the indexes of the remaining moves stay valid
+    for (type_id, idx, extended) in moves.into_iter().rev() {
+        let extended = extended.unwrap_or_else(|| {
+            let name = index.types[type_id].name.clone();
+
+            index.add_type(None, &name)
+        });
+
+        if extended != type_id {
+            let declaration = index.types[type_id].declarations.remove(idx);
+            index.types[extended].declarations.push(declaration);
+            moved = true;
+        }
+    }
+
+    moved
+}
+
+/// References to names declared in several of the imported modules
+pub fn ambiguous_references(index: &Index) -> Vec<AmbiguousReference> {
+    let mut ambiguous = vec![];
+
+    for (type_id, t) in index.types.iter().enumerate() {
+        for (idx, declaration) in t.declarations.iter().enumerate() {
+            for &(dependency, point, _, _) in &declaration.dependencies {
+                let name = &index.types[dependency].name;
+
+                if let Resolution::Ambiguous(candidates) =
+                    resolve(index, name, Some(&t.name), declaration.file)
+                {
+                    if !candidates.contains(&dependency) {
+                        ambiguous.push(AmbiguousReference {
+                            from: type_id,
+                            declaration: idx,
+                            point,
+                            candidates,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    ambiguous
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::index::{ReferenceKind, TargetKind};
+
+    // Core and Legacy both declare a Router
+    fn modules() -> Index {
+        let mut index = Index::new();
+
+        let app = index.add_package("App", "./App");
+        index.add_target(app, "Core", TargetKind::Regular, None, &[]);
+        index.add_target(app, "Legacy", TargetKind::Regular, None, &[]);
+        index.add_target(app, "Feature", TargetKind::Regular, None, &[]);
+
+        for module in ["Core", "Legacy"] {
+            index.add_declaration(
+                "Router",
+                Kind::Class,
+                &format!("./App/Sources/{}/Router.swift", module),
+                Point::new(1, 6),
+                &[],
+            );
+        }
+
+        index
+    }
+
+    fn add_screen(index: &mut Index, file: &str, imports: &[&str]) {
+        index.set_file_imports(file, imports);
+        index.add_declaration(
+            "Screen",
+            Kind::Struct,
+            file,
+            Point::new(3, 7),
+            &[(
+                "Router",
+                &Point::new(4, 16),
+                ReferenceKind::StoredProperty,
+                Confidence::High,
+            )],
+        );
+        index.resolve_references();
+    }
+
+    fn dependency_module(index: &Index) -> Option<&str> {
+        let screen = index.existing_type_id("Screen").unwrap();
+        let (dependency, _, _, _) = index.types[screen].declarations[0].dependencies[0];
+
+        index.types[dependency].module.as_deref()
+    }
+
+    #[test]
+    fn resolves_references_to_the_imported_module() {
+        let mut index = modules();
+        add_screen(
+            &mut index,
+            "./App/Sources/Feature/Screen.swift",
+            &["Foundation", "Legacy"],
+        );
+
+        assert_eq!(dependency_module(&index), Some("Legacy"));
+        assert_eq!(index.ambiguous_references(), vec![]);
+    }
+
+    #[test]
+    fn prefers_the_current_module_to_imported_ones() {
+        let mut index = modules();
+        add_screen(&mut index, "./App/Sources/Core/Screen.swift", &["Legacy"]);
+
+        assert_eq!(dependency_module(&index), Some("Core"));
+    }
+
+    #[test]
+    fn reports_references_declared_in_several_imported_modules() {
+        let mut index = modules();
+        add_screen(
+            &mut index,
+            "./App/Sources/Feature/Screen.swift",
+            &["Core", "Legacy"],
+        );
+
+        let screen = index.existing_type_id("Screen").unwrap();
+        let core = index.existing_type_id("Core.Router").unwrap();
+        let legacy = index.existing_type_id("Legacy.Router").unwrap();
+
+        assert_eq!(dependency_module(&index), None);
+        assert_eq!(
+            index.ambiguous_references(),
+            vec![AmbiguousReference {
+                from: screen,
+                declaration: 0,
+                point: Point::new(4, 16),
+                candidates: vec![core, legacy],
+            }]
+        );
+    }
+
+    #[test]
+    fn resolves_references_to_the_only_module_declaring_the_type() {
+        let mut index = modules();
+        index.add_declaration(
+            "Session",
+            Kind::Class,
+            "./App/Sources/Core/Session.swift",
+            Point::new(1, 6),
+            &[],
+        );
+        // Imports naming packages rather than modules, as in Kotlin
+        index.set_file_imports("./App/Sources/Feature/Screen.swift", &["com.shop.core"]);
+        index.add_declaration(
+            "Screen",
+            Kind::Class,
+            "./App/Sources/Feature/Screen.swift",
+            Point::new(3, 6),
+            &[
+                (
+                    "Session",
+                    &Point::new(4, 16),
+                    ReferenceKind::StoredProperty,
+                    Confidence::High,
+                ),
+                (
+                    "Router",
+                    &Point::new(5, 16),
+                    ReferenceKind::StoredProperty,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.resolve_references();
+
+        assert_eq!(dependency_module(&index), Some("Core"));
+        // Router is declared in both Core and Legacy
+        let screen = index.existing_type_id("Screen").unwrap();
+        let (router, _, _, _) = index.types[screen].declarations[0].dependencies[1];
+
+        assert_eq!(index.types[router].module, None);
+    }
+
+    #[test]
+    fn resolves_references_to_modules_imported_by_a_bridging_header() {
+        let mut index = modules();
+        index.set_file_imports(
+            "./App/Sources/Feature/Feature-Bridging-Header.h",
+            &["Legacy"],
+        );
+        add_screen(&mut index, "./App/Sources/Feature/Views/Screen.swift", &[]);
+
+        assert_eq!(dependency_module(&index), Some("Legacy"));
+    }
+
+    #[test]
+    fn resolves_references_to_the_imported_file_module() {
+        let mut index = Index::new();
+
+        for module in ["./web/admin/router", "./web/shop/router"] {
+            let file = format!("{}.ts", module);
+
+            index.set_file_module(&file, Some(module));
+            index.add_declaration("Router", Kind::Class, &file, Point::new(1, 13), &[]);
+        }
+
+        index.set_file_module("./web/shop/screen.ts", Some("./web/shop/screen"));
+        add_screen(&mut index, "./web/shop/screen.ts", &["./web/shop/router"]);
+
+        assert_eq!(dependency_module(&index), Some("./web/shop/router"));
+        assert!(index.existing_type_id("./web/admin/router.Router").is_ok());
+    }
+
+    #[test]
+    fn moves_extensions_to_the_imported_type() {
+        let mut index = modules();
+        index.set_file_imports("./App/Sources/Feature/Router+Screen.swift", &["Legacy"]);
+        index.add_declaration(
+            "Router",
+            Kind::Extension,
+            "./App/Sources/Feature/Router+Screen.swift",
+            Point::new(1, 10),
+            &[],
+        );
+        index.resolve_references();
+
+        let legacy = index.existing_type_id("Legacy.Router").unwrap();
+
+        assert_eq!(index.types[legacy].declarations.len(), 2);
+    }
+
+    #[test]
+    fn keeps_names_in_expressions_referring_to_declared_types() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "Screen",
+            Kind::Struct,
+            "./Screen.swift",
+            Point::new(1, 7),
+            &[
+                (
+                    "Formatter",
+                    &Point::new(2, 20),
+                    ReferenceKind::BodyCall,
+                    Confidence::Medium,
+                ),
+                (
+                    "Timeout",
+                    &Point::new(3, 20),
+                    ReferenceKind::BodyCall,
+                    Confidence::Medium,
+                ),
+                (
+                    "UIColor",
+                    &Point::new(4, 20),
+                    ReferenceKind::BodyCall,
+                    Confidence::Medium,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "Timeout",
+            Kind::Constant,
+            "./Constants.swift",
+            Point::new(1, 4),
+            &[],
+        );
+        index.add_declaration(
+            "Formatter",
+            Kind::Struct,
+            "./Formatter.swift",
+            Point::new(1, 7),
+            &[],
+        );
+        index.resolve_references();
+
+        let screen = index.existing_type_id("Screen").unwrap();
+        let formatter = index.existing_type_id("Formatter").unwrap();
+        let declaration = &index.types[screen].declarations[0];
+
+        assert_eq!(
+            declaration.dependencies,
+            vec![(
+                formatter,
+                Point::new(2, 20),
+                ReferenceKind::BodyCall,
+                Confidence::Medium
+            )]
+        );
+        assert_eq!(index.dependents_of(formatter), &[(screen, 0)]);
+
+        // Once the type is gone, the name is no longer a reference
+        index.remove_files(&["./Formatter.swift"]);
+        index.resolve_references();
+
+        assert_eq!(index.types[screen].declarations[0].dependencies, vec![]);
+    }
+}
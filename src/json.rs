@@ -0,0 +1,446 @@
+// JSON output of the query commands
+//
+// The schema is documented in the README. Any breaking change to it must bump
+// SCHEMA_VERSION, additive changes (new fields) do not.
+
+use serde::Serialize;
+use tree_sitter::Point;
+
+use crate::index::{
+    Confidence, Index, IndexItem, Kind, PackageEdge, PackageId, ReferenceKind, TypeOrigin,
+};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Location {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl From<Point> for Location {
+    fn from(point: Point) -> Self {
+        Self {
+            row: point.row,
+            column: point.column,
+        }
+    }
+}
+
+/// Output of `drake deps --format json`
+#[derive(Debug, Serialize)]
+pub struct Dependencies<'a> {
+    pub version: u32,
+    /// Type the walk started from
+    pub root: &'a str,
+    /// Every type reached by the walk, in the order they were first reached
+    pub types: Vec<TypeEntry<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeEntry<'a> {
+    pub name: &'a str,
+    pub origin: TypeOrigin,
+    pub declarations: Vec<DeclarationEntry<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeclarationEntry<'a> {
+    pub kind: Kind,
+    pub file: String,
+    /// Package the declaring file belongs to, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<&'a str>,
+    /// Target of the package the declaring file belongs to, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<&'a str>,
+    #[serde(flatten)]
+    pub location: Location,
+    pub dependencies: Vec<DependencyEntry<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyEntry<'a> {
+    #[serde(rename = "type")]
+    pub type_name: &'a str,
+    pub origin: TypeOrigin,
+    /// Locations of the references in the declaring file
+    pub references: Vec<Location>,
+    /// Kinds of the references
+    pub kinds: Vec<ReferenceKind>,
+    /// Confidence in the most certain of the references
+    pub confidence: Confidence,
+}
+
+impl<'a> Dependencies<'a> {
+    pub fn new(
+        index: &'a Index,
+        type_name: &'a str,
+        include_external: bool,
+        package: Option<PackageId>,
+        kinds: &[ReferenceKind],
+    ) -> anyhow::Result<Self> {
+        let mut cursor = index.walk(type_name)?;
+
+        if let Some(package) = package {
+            cursor = cursor.in_package(package);
+        }
+
+        if !kinds.is_empty() {
+            cursor = cursor.of_kinds(kinds);
+        }
+
+        let mut types = vec![];
+
+        for (item, _) in cursor {
+            let IndexItem::Type(type_id, name, origin) = item else {
+                continue;
+            };
+
+            if origin == TypeOrigin::External && !include_external {
+                continue;
+            }
+
+            let t = index
+                .get_type(type_id)
+                .expect("index refers to an unknown type");
+
+            let declarations = t
+                .declarations
+                .iter()
+                .filter(|declaration| package.is_none() || index.package_id(declaration) == package)
+                .map(|declaration| {
+                    let mut dependencies: Vec<_> = declaration
+                        .dependencies()
+                        .into_iter()
+                        .filter_map(|(dependency_id, points)| {
+                            let dependency = index
+                                .get_type(dependency_id)
+                                .expect("index refers to an unknown type");
+                            let origin = dependency.origin();
+
+                            if origin == TypeOrigin::External && !include_external {
+                                return None;
+                            }
+
+                            let reference_kinds = index.reference_kinds(declaration, dependency_id);
+
+                            if !kinds.is_empty()
+                                && !kinds.iter().any(|k| reference_kinds.contains(k))
+                            {
+                                return None;
+                            }
+
+                            Some(DependencyEntry {
+                                type_name: dependency.name.as_ref(),
+                                origin,
+                                references: points.into_iter().map(Location::from).collect(),
+                                kinds: reference_kinds.into_iter().collect(),
+                                confidence: index
+                                    .reference_confidence(declaration, dependency_id)
+                                    .expect("declaration refers to the dependency"),
+                            })
+                        })
+                        .collect();
+
+                    // Type ids follow the order files were scanned in, which changes
+                    // between runs
+                    dependencies.sort_by_key(|dependency| {
+                        let first = dependency.references.first();
+
+                        (
+                            dependency.type_name,
+                            first.map(|location| (location.row, location.column)),
+                        )
+                    });
+
+                    DeclarationEntry {
+                        kind: declaration.kind,
+                        file: index
+                            .file_path(declaration)
+                            .expect("index refers to an unknown file"),
+                        package: index.package_name(declaration),
+                        target: index.target_name(declaration),
+                        location: declaration.point.into(),
+                        dependencies,
+                    }
+                })
+                .collect();
+
+            types.push(TypeEntry {
+                name,
+                origin,
+                declarations,
+            });
+        }
+
+        Ok(Self {
+            version: SCHEMA_VERSION,
+            root: type_name,
+            types,
+        })
+    }
+}
+
+/// Output of `drake packages graph --format json`
+#[derive(Debug, Serialize)]
+pub struct PackageGraph<'a> {
+    pub version: u32,
+    pub packages: Vec<PackageNode<'a>>,
+    pub dependencies: Vec<PackageDependency<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageNode<'a> {
+    pub name: &'a str,
+    pub path: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageDependency<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    /// Number of type references from one package to the other
+    pub references: usize,
+    pub examples: Vec<PackageExample<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageExample<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub file: String,
+    #[serde(flatten)]
+    pub location: Location,
+}
+
+impl<'a> PackageGraph<'a> {
+    pub fn new(index: &'a Index, edges: &[PackageEdge]) -> Self {
+        let package_name = |package_id| {
+            index
+                .get_package(package_id)
+                .expect("index refers to an unknown package")
+                .name()
+        };
+        let type_name = |type_id| {
+            index
+                .get_type(type_id)
+                .expect("index refers to an unknown type")
+                .name
+                .as_str()
+        };
+
+        let packages = index
+            .packages()
+            .map(|(_, package)| PackageNode {
+                name: package.name(),
+                path: package.path_prefix(),
+            })
+            .collect();
+
+        let dependencies = edges
+            .iter()
+            .map(|edge| PackageDependency {
+                from: package_name(edge.from),
+                to: package_name(edge.to),
+                references: edge.count,
+                examples: edge
+                    .examples
+                    .iter()
+                    .map(|example| {
+                        let declaration = &index
+                            .get_type(example.from)
+                            .expect("index refers to an unknown type")
+                            .declarations[example.declaration];
+
+                        PackageExample {
+                            from: type_name(example.from),
+                            to: type_name(example.to),
+                            file: index
+                                .file_path(declaration)
+                                .expect("index refers to an unknown file"),
+                            location: example.point.into(),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            version: SCHEMA_VERSION,
+            packages,
+            dependencies,
+        }
+    }
+}
+
+/// Output of `drake print --format json`
+#[derive(Debug, Serialize)]
+pub struct Files {
+    pub version: u32,
+    pub files: Vec<File>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct File {
+    pub path: String,
+    /// Only present when declarations were requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declarations: Option<Vec<FileDeclaration>>,
+    /// Only present when the full parse tree was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tree: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileDeclaration {
+    pub kind: &'static str,
+    pub name: String,
+    #[serde(flatten)]
+    pub location: Location,
+    /// Only present when references were requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub references: Option<Vec<FileReference>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileReference {
+    pub name: String,
+    #[serde(flatten)]
+    pub location: Location,
+    pub kind: ReferenceKind,
+    pub confidence: Confidence,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn serializes_walked_dependencies() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(10, 20),
+            &[
+                (
+                    "OtherType",
+                    &Point::new(11, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "ExternalType",
+                    &Point::new(12, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "OtherType",
+                    &Point::new(13, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "OtherType",
+            Kind::Protocol,
+            "./OtherType.swift",
+            Point::new(1, 9),
+            &[],
+        );
+
+        let actual = Dependencies::new(&index, "MyType", false, None, &[]).unwrap();
+        let expected = json!({
+            "version": 1,
+            "root": "MyType",
+            "types": [
+                {
+                    "name": "MyType",
+                    "origin": "local",
+                    "declarations": [
+                        {
+                            "kind": "struct",
+                            "file": "./MyType.swift",
+                            "row": 10,
+                            "column": 20,
+                            "dependencies": [
+                                {
+                                    "type": "OtherType",
+                                    "origin": "local",
+                                    "kinds": ["body-call"],
+                                    "confidence": "high",
+                                    "references": [
+                                        { "row": 11, "column": 10 },
+                                        { "row": 13, "column": 10 }
+                                    ]
+                                }
+                            ]
+                        }
+                    ]
+                },
+                {
+                    "name": "OtherType",
+                    "origin": "local",
+                    "declarations": [
+                        {
+                            "kind": "protocol",
+                            "file": "./OtherType.swift",
+                            "row": 1,
+                            "column": 9,
+                            "dependencies": []
+                        }
+                    ]
+                }
+            ]
+        });
+
+        assert_eq!(serde_json::to_value(actual).unwrap(), expected);
+    }
+
+    #[test]
+    fn orders_dependencies_by_type_name() {
+        let mut index = Index::new();
+        index.add_declaration(
+            "MyType",
+            Kind::Struct,
+            "./MyType.swift",
+            Point::new(0, 7),
+            &[
+                (
+                    "Zebra",
+                    &Point::new(1, 10),
+                    ReferenceKind::StoredProperty,
+                    Confidence::High,
+                ),
+                (
+                    "Apple",
+                    &Point::new(2, 10),
+                    ReferenceKind::StoredProperty,
+                    Confidence::High,
+                ),
+                (
+                    "Mango",
+                    &Point::new(3, 10),
+                    ReferenceKind::StoredProperty,
+                    Confidence::High,
+                ),
+            ],
+        );
+
+        let actual = Dependencies::new(&index, "MyType", true, None, &[]).unwrap();
+        let names: Vec<_> = actual.types[0].declarations[0]
+            .dependencies
+            .iter()
+            .map(|dependency| dependency.type_name)
+            .collect();
+
+        assert_eq!(names, vec!["Apple", "Mango", "Zebra"]);
+    }
+}
@@ -0,0 +1,251 @@
+// Checking the references between types against the layering rules in the config
+
+use anyhow::Context;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tree_sitter::Point;
+
+use crate::config::{Config, Layer};
+use crate::index::{Declaration, Index};
+
+/// A reference from a type in one layer to a type in a layer it may not use
+#[derive(Debug, PartialEq)]
+pub struct Violation<'a> {
+    pub from_layer: &'a str,
+    pub to_layer: &'a str,
+    pub type_name: &'a str,
+    pub dependency_name: &'a str,
+    pub file: String,
+    /// Location of the reference
+    pub point: Point,
+}
+
+pub struct Layers<'a> {
+    layers: Vec<(&'a Layer, GlobSet)>,
+    // Path the layer paths are relative to
+    root: &'a str,
+}
+
+impl<'a> Layers<'a> {
+    pub fn new(config: &'a Config, root: &'a str) -> anyhow::Result<Self> {
+        let mut layers = vec![];
+
+        for layer in &config.layers {
+            let mut builder = GlobSetBuilder::new();
+
+            for path in &layer.paths {
+                let glob = Glob::new(path.trim_start_matches("./"))
+                    .with_context(|| format!("Invalid path {} in layer {}", path, layer.name))?;
+
+                builder.add(glob);
+            }
+
+            layers.push((layer, builder.build()?));
+        }
+
+        Ok(Self { layers, root })
+    }
+
+    /// Find all references breaking the layering rules, sorted by file and location
+    pub fn check(&self, index: &'a Index) -> Vec<Violation<'a>> {
+        let mut violations = vec![];
+
+        for (_, t) in index.types() {
+            for declaration in &t.declarations {
+                let Some(from) = self.layer_of(index, declaration) else {
+                    continue;
+                };
+
+                for (dependency_id, points) in declaration.dependencies() {
+                    let dependency = index
+                        .get_type(dependency_id)
+                        .expect("index refers to an unknown type");
+
+                    let Some(to) = dependency
+                        .primary_declaration()
+                        .and_then(|declaration| self.layer_of(index, declaration))
+                    else {
+                        continue;
+                    };
+
+                    if from.name == to.name || may_use(from, to) {
+                        continue;
+                    }
+
+                    let file = index
+                        .file_path(declaration)
+                        .expect("index refers to an unknown file");
+
+                    for point in points {
+                        violations.push(Violation {
+                            from_layer: &from.name,
+                            to_layer: &to.name,
+                            type_name: &t.name,
+                            dependency_name: &dependency.name,
+                            file: file.clone(),
+                            point,
+                        });
+                    }
+                }
+            }
+        }
+
+        violations.sort_by(|a, b| (&a.file, a.point).cmp(&(&b.file, b.point)));
+
+        violations
+    }
+
+    // The first layer listing the declaration's package or matching its path
+    fn layer_of(&self, index: &Index, declaration: &Declaration) -> Option<&'a Layer> {
+        let path = index.file_path(declaration)?;
+        let path = relative_path(&path, self.root);
+        let package = index.package_name(declaration);
+
+        self.layers
+            .iter()
+            .find(|(layer, globs)| {
+                package.is_some_and(|package| layer.packages.iter().any(|p| p == package))
+                    || globs.is_match(path)
+            })
+            .map(|(layer, _)| *layer)
+    }
+}
+
+fn may_use(from: &Layer, to: &Layer) -> bool {
+    let allowed = match &from.allow {
+        Some(allow) => allow.contains(&to.name),
+        None => true,
+    };
+
+    allowed && !from.forbid.contains(&to.name)
+}
+
+// Path of a scanned file relative to the scanned path
+fn relative_path<'p>(path: &'p str, root: &str) -> &'p str {
+    let relative = match path.strip_prefix(root) {
+        Some(rest) if rest.starts_with('/') || root.ends_with('/') => rest,
+        _ => path,
+    };
+
+    relative.trim_start_matches("./").trim_start_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::index::{Confidence, Kind, ReferenceKind};
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [[layers]]
+            name = "UI"
+            paths = ["App/**"]
+            forbid = ["Data"]
+
+            [[layers]]
+            name = "Domain"
+            packages = ["Core"]
+            allow = []
+
+            [[layers]]
+            name = "Data"
+            paths = ["Storage/**"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reports_forbidden_and_not_allowed_references() {
+        let mut index = Index::new();
+        index.add_package("Core", "./Packages/Core");
+        index.add_declaration(
+            "LoginView",
+            Kind::Struct,
+            "./App/LoginView.swift",
+            Point::new(3, 7),
+            &[
+                (
+                    "Session",
+                    &Point::new(4, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "Database",
+                    &Point::new(5, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+                (
+                    "Text",
+                    &Point::new(6, 10),
+                    ReferenceKind::BodyCall,
+                    Confidence::High,
+                ),
+            ],
+        );
+        index.add_declaration(
+            "Session",
+            Kind::Struct,
+            "./Packages/Core/Session.swift",
+            Point::new(1, 7),
+            &[(
+                "Database",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+        index.add_declaration(
+            "Database",
+            Kind::Class,
+            "./Storage/Database.swift",
+            Point::new(1, 6),
+            &[(
+                "Session",
+                &Point::new(2, 10),
+                ReferenceKind::BodyCall,
+                Confidence::High,
+            )],
+        );
+
+        let config = config();
+        let layers = Layers::new(&config, ".").unwrap();
+
+        let actual: Vec<_> = layers
+            .check(&index)
+            .into_iter()
+            .map(|v| (v.file, v.point.row, v.from_layer, v.to_layer))
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                ("./App/LoginView.swift".to_string(), 5, "UI", "Data"),
+                (
+                    "./Packages/Core/Session.swift".to_string(),
+                    2,
+                    "Domain",
+                    "Data"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_paths_relative_to_the_scanned_path() {
+        assert_eq!(relative_path("./App/View.swift", "."), "App/View.swift");
+        assert_eq!(
+            relative_path("Sources/App/View.swift", "Sources"),
+            "App/View.swift"
+        );
+        assert_eq!(relative_path("App/View.swift", "Other"), "App/View.swift");
+        assert_eq!(
+            relative_path("AppKit/View.swift", "App"),
+            "AppKit/View.swift"
+        );
+    }
+}
@@ -1,25 +1,63 @@
+mod config;
+mod export;
 mod index;
+mod json;
+mod layers;
 mod parser;
 mod worker_pool;
 
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{self, Component, Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
 
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, Context};
 
-use ignore::{types::TypesBuilder, WalkBuilder};
+use clap::ValueEnum;
+use ignore::{types::TypesBuilder, WalkBuilder, WalkParallel};
 use index::{Declaration, Index, IndexItem};
-use parser::{Definition, Tree};
-use tree_sitter::Point;
+use parser::{CustomQueries, Definition, Language, Manifest, Parser, QueryFile, Tree, LANGUAGES};
 
-use crate::index::{IndexCursor, Kind, Type, TypeId, TypeOrigin};
+use crate::config::{Config, CONFIG_FILE};
+pub use crate::export::{Cluster, GraphFormat};
+pub use crate::index::ReferenceKind;
+use crate::index::{
+    Confidence, FileStamp, Kind, PackageId, PackageReference, SyntaxError, TargetKind, TypeId,
+    TypeOrigin,
+};
 
 // Package definition
 #[derive(Debug)]
 pub struct Package {
-    name: String,
+    manifest: Manifest,
     prefix: PathBuf,
 }
 
+/// Output format of the query commands
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human readable, indented text
+    #[default]
+    Text,
+    /// JSON following the schema documented in the README
+    Json,
+}
+
+/// Output format of the package graph
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PackageGraphFormat {
+    /// Human readable text, with example references
+    #[default]
+    Text,
+    /// JSON following the schema documented in the README
+    Json,
+    /// Graphviz DOT
+    Dot,
+}
+
 #[derive(Default)]
 pub struct Drake {
     index: Index,
@@ -34,14 +72,47 @@ impl Drake {
         }
     }
 
-    pub fn print(&mut self, path: &str, decl: bool, refs: bool, full: bool) -> anyhow::Result<()> {
-        let mut builder = TypesBuilder::new();
-        builder.add_defaults();
+    pub fn print(
+        &mut self,
+        path: &str,
+        decl: bool,
+        refs: bool,
+        full: bool,
+        format: Format,
+    ) -> anyhow::Result<()> {
+        let parsers = parsers(path)?;
+        let walk = walk_files(path, |language| language.file_globs())?;
+
+        if format == Format::Json {
+            let results = worker_pool::process_files(walk, parsers, move |path, parser| {
+                let source = fs::read_to_string(path)?;
+                let tree = parser.parse(source)?;
+
+                file_entry(&path.to_string_lossy(), tree, decl, refs, full)
+            });
+
+            let mut files = vec![];
+
+            for file in results {
+                match file {
+                    Ok(file) => files.push(file),
+                    Err(e) => eprintln!("Could not process file: {e}"),
+                }
+            }
+
+            // Files are parsed in parallel, in no particular order
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+
+            let output = json::Files {
+                version: json::SCHEMA_VERSION,
+                files,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
 
-        let matcher = builder.select("swift").build()?;
-        let walk = WalkBuilder::new(path).types(matcher).build_parallel();
+            return Ok(());
+        }
 
-        let results = worker_pool::process_files(walk, move |path, parser| {
+        let results = worker_pool::process_files(walk, parsers, move |path, parser| {
             let source = fs::read_to_string(path)?;
             let tree = parser.parse(source)?;
 
@@ -68,10 +139,33 @@ impl Drake {
         &self,
         type_name: &str,
         include_external: bool,
+        package: Option<&str>,
+        kinds: &[ReferenceKind],
+        format: Format,
     ) -> anyhow::Result<()> {
+        let package = package.map(|name| self.find_package(name)).transpose()?;
+
+        if format == Format::Json {
+            let output =
+                json::Dependencies::new(&self.index, type_name, include_external, package, kinds)?;
+            println!("{}", serde_json::to_string_pretty(&output)?);
+
+            return Ok(());
+        }
+
+        let mut cursor = self.index.walk(type_name)?;
+
+        if let Some(package) = package {
+            cursor = cursor.in_package(package);
+        }
+
+        if !kinds.is_empty() {
+            cursor = cursor.of_kinds(kinds);
+        }
+
         let mut current_declaration: Option<&Declaration> = None;
 
-        for (item, depth) in self.index.walk(type_name)? {
+        for (item, depth) in cursor {
             let d = depth - (depth / 3);
             let prefix = "  ".repeat(d);
 
@@ -94,7 +188,7 @@ impl Drake {
                                 .collect::<Vec<_>>()
                                 .join(", ");
 
-                            format!(" ({})", ps)
+                            format!(" ({} at {})", self.describe_references(declaration, id), ps)
                         } else {
                             "".to_string()
                         };
@@ -107,20 +201,11 @@ impl Drake {
                 IndexItem::Declaration(declaration) => {
                     current_declaration = Some(declaration);
 
-                    let kind = match declaration.kind {
-                        Kind::Struct => "struct declared",
-                        Kind::Enum => "enum declared",
-                        Kind::Class => "class declared",
-                        Kind::Protocol => "protocol declared",
-                        Kind::Extension => "extended",
-                    };
-                    let point = format!("{}:{}", declaration.point.row, declaration.point.column);
-                    let path = self
-                        .index
-                        .file_path(declaration)
-                        .expect("index refers to an unknown file");
-
-                    println!("{}{} in {} {}, using types:", prefix, kind, path, point)
+                    println!(
+                        "{}{}, using types:",
+                        prefix,
+                        self.describe_declaration(declaration)
+                    )
                 }
                 _ => (),
             }
@@ -129,105 +214,1054 @@ impl Drake {
         Ok(())
     }
 
-    // Builds the type index
+    pub fn print_dependents(
+        &self,
+        type_name: &str,
+        package: Option<&str>,
+        kinds: &[ReferenceKind],
+    ) -> anyhow::Result<()> {
+        let mut cursor = self.index.walk_dependents(type_name)?;
+
+        if let Some(name) = package {
+            cursor = cursor.in_package(self.find_package(name)?);
+        }
+
+        if !kinds.is_empty() {
+            cursor = cursor.of_kinds(kinds);
+        }
+
+        println!("Types depending on {}:", type_name);
+
+        for (dependent, depth) in cursor {
+            let prefix = "  ".repeat(depth);
+            let declaration = dependent.declaration;
+
+            let dependency = self
+                .index
+                .get_type(dependent.dependency)
+                .expect("index refers to an unknown type");
+            let locations = declaration
+                .dependencies()
+                .get(&dependent.dependency)
+                .map(|points| {
+                    points
+                        .iter()
+                        .map(|point| format!("{}:{}", point.row, point.column))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+
+            println!(
+                "{}- {}, {}, using {} as {} at {}",
+                prefix,
+                dependent.name,
+                self.describe_declaration(declaration),
+                dependency.name,
+                self.describe_references(declaration, dependent.dependency),
+                locations
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Print the superclasses and protocols of a type, or with `down` its subclasses and
+    /// the types conforming to it
+    pub fn print_hierarchy(&self, type_name: &str, down: bool) -> anyhow::Result<()> {
+        let entries = if down {
+            println!("Subtypes of {}:", type_name);
+
+            self.index.subtypes(type_name)?
+        } else {
+            println!("Supertypes of {}:", type_name);
+
+            self.index.supertypes(type_name)?
+        };
+
+        // Names of the types on the way to the current entry
+        let mut related = vec![type_name];
+
+        for entry in entries {
+            let t = self
+                .index
+                .get_type(entry.type_id)
+                .expect("index refers to an unknown type");
+            let (declaring_type, idx) = entry.declaration;
+            let declaration = &self
+                .index
+                .get_type(declaring_type)
+                .expect("index refers to an unknown type")
+                .declarations[idx];
+
+            related.truncate(entry.depth + 1);
+
+            let relationship = match (down, entry.kind) {
+                (false, ReferenceKind::Inheritance) => "superclass of",
+                (false, _) => "adopted by",
+                (true, ReferenceKind::Inheritance) => "subclass of",
+                (true, _) => "conforms to",
+            };
+            let postfix = match t.origin() {
+                TypeOrigin::External => " (external)",
+                TypeOrigin::Local => "",
+            };
+
+            println!(
+                "{}- {}{}, {} {} ({})",
+                "  ".repeat(entry.depth),
+                t.name,
+                postfix,
+                relationship,
+                related[entry.depth],
+                self.describe_declaration(declaration)
+            );
+
+            related.push(&t.name);
+        }
+
+        Ok(())
+    }
+
+    pub fn export(
+        &self,
+        type_name: Option<&str>,
+        include_external: bool,
+        format: GraphFormat,
+        cluster: Option<Cluster>,
+    ) -> anyhow::Result<()> {
+        let graph = export::Graph::new(&self.index, type_name, include_external, cluster)?;
+
+        print!("{}", graph.render(format));
+
+        Ok(())
+    }
+
+    pub fn print_package_graph(&self, format: PackageGraphFormat) -> anyhow::Result<()> {
+        let edges = self.index.package_graph();
+
+        match format {
+            PackageGraphFormat::Json => {
+                let output = json::PackageGraph::new(&self.index, &edges);
+                println!("{}", serde_json::to_string_pretty(&output)?);
+
+                return Ok(());
+            }
+            PackageGraphFormat::Dot => {
+                print!("{}", export::package_dot(&self.index, &edges));
+
+                return Ok(());
+            }
+            PackageGraphFormat::Text => (),
+        }
+
+        let package_name = |package_id| {
+            self.index
+                .get_package(package_id)
+                .expect("index refers to an unknown package")
+                .name()
+        };
+
+        println!(
+            "Found {} packages with {} dependencies between them.",
+            self.index.packages().count(),
+            edges.len()
+        );
+
+        for edge in &edges {
+            println!(
+                "\n- {} -> {} ({} references)",
+                package_name(edge.from),
+                package_name(edge.to),
+                edge.count
+            );
+
+            self.print_examples(&edge.examples, edge.count);
+        }
+
+        Ok(())
+    }
+
+    pub fn print_manifest_check(&self) -> anyhow::Result<()> {
+        let report = self.index.check_manifests();
+
+        let target_name = |target_id| {
+            let target = self
+                .index
+                .get_target(target_id)
+                .expect("index refers to an unknown target");
+            let package = self
+                .index
+                .get_package(target_id.0)
+                .expect("index refers to an unknown package");
+
+            format!("{} (package {})", target.name, package.name())
+        };
+
+        println!("Found {} missing dependencies.", report.missing.len());
+
+        for missing in &report.missing {
+            println!(
+                "\n- {} uses {} ({} references)",
+                target_name(missing.from),
+                target_name(missing.to),
+                missing.count
+            );
+
+            self.print_examples(&missing.examples, missing.count);
+        }
+
+        println!("\nFound {} unused dependencies.", report.unused.len());
+
+        for unused in &report.unused {
+            let target = self
+                .index
+                .get_target(unused.target)
+                .expect("index refers to an unknown target");
+            let dependency = &target.dependencies[unused.dependency];
+
+            match &dependency.package {
+                Some(package) => println!(
+                    "- {} depends on product {} of {}",
+                    target_name(unused.target),
+                    dependency.name,
+                    package
+                ),
+                None => println!(
+                    "- {} depends on {}",
+                    target_name(unused.target),
+                    dependency.name
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check references between types against the layering rules in a config file,
+    /// `.drake.toml` in the scanned path by default. Returns the number of violations.
+    pub fn check_layers(&self, path: &str, config: Option<&Path>) -> anyhow::Result<usize> {
+        let config = match config {
+            Some(config) => Config::load(config)?,
+            None => Config::load(&Path::new(path).join(CONFIG_FILE))?,
+        };
+
+        if config.layers.is_empty() {
+            bail!("No layers are defined in the config");
+        }
+
+        let violations = layers::Layers::new(&config, path)?.check(&self.index);
+
+        for violation in &violations {
+            // Rows and columns from one, like compilers report them
+            println!(
+                "{}:{}:{}: {} in layer {} uses {} in layer {}",
+                violation.file,
+                violation.point.row + 1,
+                violation.point.column + 1,
+                violation.type_name,
+                violation.from_layer,
+                violation.dependency_name,
+                violation.to_layer
+            );
+        }
+
+        Ok(violations.len())
+    }
+
+    pub fn print_cycles(&self, cross_package: bool) -> anyhow::Result<()> {
+        let mut cycles = self.index.cycles();
+
+        if cross_package {
+            cycles.retain(|cycle| self.index.declaring_packages(cycle).len() > 1);
+        }
+
+        println!("Found {} cycles.", cycles.len());
+
+        for (idx, cycle) in cycles.iter().enumerate() {
+            let types: Vec<_> = cycle
+                .iter()
+                .map(|&type_id| {
+                    self.index
+                        .get_type(type_id)
+                        .expect("index refers to an unknown type")
+                })
+                .collect();
+
+            let names: Vec<_> = types.iter().map(|t| t.name.as_str()).collect();
+            println!("\nCycle {}: {}", idx + 1, names.join(", "));
+
+            for &type_id in cycle {
+                for &dependency_id in cycle {
+                    if dependency_id != type_id {
+                        self.print_edge(type_id, dependency_id);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn print_paths(&self, from: &str, to: &str, limit: Option<usize>) -> anyhow::Result<()> {
+        let paths = match limit {
+            Some(limit) => self.index.simple_paths(from, to, limit)?,
+            None => self.index.shortest_path(from, to)?.into_iter().collect(),
+        };
+
+        if paths.is_empty() {
+            println!("{} does not depend on {}.", from, to);
+
+            return Ok(());
+        }
+
+        for (idx, path) in paths.iter().enumerate() {
+            if idx > 0 {
+                println!();
+            }
+
+            println!("Path {} ({} hops):", idx + 1, path.len() - 1);
+
+            for hop in path.windows(2) {
+                self.print_edge(hop[0], hop[1]);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Prints the declarations of a type referencing another type, if there are any
+    fn print_edge(&self, type_id: TypeId, dependency_id: TypeId) {
+        let t = self
+            .index
+            .get_type(type_id)
+            .expect("index refers to an unknown type");
+        let dependency = self
+            .index
+            .get_type(dependency_id)
+            .expect("index refers to an unknown type");
+
+        for declaration in &t.declarations {
+            let Some(points) = declaration.dependencies().remove(&dependency_id) else {
+                continue;
+            };
+
+            let locations = points
+                .iter()
+                .map(|point| format!("{}:{}", point.row, point.column))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            println!("- {} -> {}", t.name, dependency.name);
+            println!(
+                "  {}, as {} at {}",
+                self.describe_declaration(declaration),
+                self.describe_references(declaration, dependency_id),
+                locations
+            );
+        }
+    }
+
+    // Kinds of the references a declaration makes to a type, e.g. "superclass, body call",
+    // noting references only guessed from expressions
+    fn describe_references(&self, declaration: &Declaration, type_id: TypeId) -> String {
+        let kinds = self
+            .index
+            .reference_kinds(declaration, type_id)
+            .into_iter()
+            .map(reference_kind_description)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match self.index.reference_confidence(declaration, type_id) {
+            Some(Confidence::Medium) => format!("{} (medium confidence)", kinds),
+            _ => kinds,
+        }
+    }
+
+    // Prints example references between types, with an ellipsis if there are more
+    fn print_examples(&self, examples: &[PackageReference], count: usize) {
+        for example in examples {
+            let t = self
+                .index
+                .get_type(example.from)
+                .expect("index refers to an unknown type");
+            let dependency = self
+                .index
+                .get_type(example.to)
+                .expect("index refers to an unknown type");
+            let path = self
+                .index
+                .file_path(&t.declarations[example.declaration])
+                .expect("index refers to an unknown file");
+
+            println!(
+                "  {} -> {} in {} {}:{}",
+                t.name, dependency.name, path, example.point.row, example.point.column
+            );
+        }
+
+        if count > examples.len() {
+            println!("  ...");
+        }
+    }
+
+    // Kind and location of a declaration, with the package it was made in if known
+    fn describe_declaration(&self, declaration: &Declaration) -> String {
+        let kind = kind_description(declaration.kind);
+        let point = format!("{}:{}", declaration.point.row, declaration.point.column);
+        let path = self
+            .index
+            .file_path(declaration)
+            .expect("index refers to an unknown file");
+
+        match (
+            self.index.package_name(declaration),
+            self.index.target_name(declaration),
+        ) {
+            (Some(package), Some(target)) => format!(
+                "{} in {} {} (package {}, target {})",
+                kind, path, point, package, target
+            ),
+            (Some(package), None) => {
+                format!("{} in {} {} (package {})", kind, path, point, package)
+            }
+            _ => format!("{} in {} {}", kind, path, point),
+        }
+    }
+
+    fn find_package(&self, name: &str) -> anyhow::Result<PackageId> {
+        self.index
+            .package_id_by_name(name)
+            .ok_or_else(|| anyhow!("Package {} not found in the index.", name))
+    }
+
+    /// Save the index built by `scan` to a file
+    pub fn save_index(&self, path: &Path) -> anyhow::Result<()> {
+        self.index.save(path)?;
+
+        eprintln!("Saved index to {}.", path.display());
+
+        Ok(())
+    }
+
+    /// Replace the index with one saved by `save_index`
+    pub fn load_index(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.index = Index::load(path)?;
+
+        Ok(())
+    }
+
+    // Builds the type index, only parsing files which changed since they were last scanned
     pub fn scan(&mut self, path: &str) -> anyhow::Result<()> {
-        let mut builder = TypesBuilder::new();
-        builder.add_defaults();
+        let stats = self.scan_files(path)?;
+
+        eprintln!(
+            "Scanned {} files ({} changed, {} removed).",
+            stats.files, stats.changed, stats.removed
+        );
+        eprintln!(
+            "Searching {} new declarations and {} references.",
+            stats.declarations, stats.references
+        );
+
+        self.print_ambiguous_references();
+        self.print_syntax_error_summary();
+
+        Ok(())
+    }
+
+    // Updates the index with the files under the path which changed or were removed
+    fn scan_files(&mut self, path: &str) -> anyhow::Result<ScanStats> {
+        let parsers = parsers(path)?;
+
+        self.scan_packages(path, parsers.clone())?;
+
+        let walk = walk_files(path, |language| language.file_globs())?;
+
+        // Files parsed with other queries are parsed again, even when unchanged
+        let hashes: Vec<_> = parsers
+            .iter()
+            .map(|parser| parser.queries_hash().to_string())
+            .collect();
+        let queries_hash = content_hash(&hashes.join(","));
+        let reparse = self.index.queries_hash() != Some(queries_hash);
 
-        let matcher = builder.select("swift").build()?;
-        let walk = WalkBuilder::new(path).types(matcher).build_parallel();
+        // Previously scanned files by normalized path, a file keeps the path it was
+        // indexed with however the scanned path is spelled, e.g. `.` or absolute
+        let previous_files: Arc<HashMap<_, _>> = Arc::new(
+            self.index
+                .file_stamps()
+                .into_iter()
+                .map(|(file, stamp)| (normalize_path(Path::new(&file)), (file, stamp)))
+                .collect(),
+        );
+        let files = previous_files.clone();
+
+        let results = worker_pool::process_files(walk, parsers, move |path, parser| {
+            let indexed = files.get(&normalize_path(path));
+            let file_path = match indexed {
+                Some((file, _)) => file.clone(),
+                None => path.to_string_lossy().to_string(),
+            };
+            let metadata = fs::metadata(path)?;
+            let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+            let previous = indexed.map(|&(_, stamp)| stamp).filter(|_| !reparse);
+
+            if let Some(previous) = previous {
+                if previous.modified == modified && previous.size == metadata.len() {
+                    return Ok(ScannedFile::Unchanged(file_path, previous));
+                }
+            }
 
-        let results = worker_pool::process_files(walk, move |path, parser| {
             let source = fs::read_to_string(path)?;
+            let stamp = FileStamp {
+                modified,
+                size: metadata.len(),
+                hash: content_hash(&source),
+            };
+
+            if previous.map(|previous| previous.hash) == Some(stamp.hash) {
+                return Ok(ScannedFile::Unchanged(file_path, stamp));
+            }
+
             let tree = parser.parse(source)?;
 
-            Ok((path.to_string_lossy().to_string(), tree.declarations()?))
+            let parsed = ParsedFile {
+                module: tree.module(path),
+                imports: tree.imports(path)?,
+                declarations: tree.declarations()?,
+                errors: tree.syntax_errors(),
+            };
+
+            Ok(ScannedFile::Changed(file_path, stamp, parsed))
         });
 
-        let mut declaration_count = 0;
-        let mut references_count = 0;
+        let mut scanned_files = HashSet::new();
+        let mut changed_files = vec![];
 
         for result in results {
             match result {
-                Ok((file_path, declarations)) => {
-                    for declaration in declarations {
-                        declaration_count += 1;
-
-                        let (name, kind) = match declaration.definition {
-                            Definition::Class { kind, name } => match kind {
-                                "class" => (name, Kind::Class),
-                                "struct" => (name, Kind::Struct),
-                                "enum" => (name, Kind::Enum),
-                                x => {
-                                    eprintln!("Unknown type kind {x}");
-                                    unreachable!();
-                                }
-                            },
-                            Definition::Protocol { name } => (name, Kind::Protocol),
-                            Definition::Extension { name } => (name, Kind::Extension),
-                        };
-                        let point = declaration.location;
-                        let references: Vec<_> = declaration
-                            .references
-                            .iter()
-                            .map(|r| {
-                                references_count += 1;
-
-                                (r.name.as_str(), &r.location)
-                            })
-                            .collect();
-
-                        self.index
-                            .add_declaration(&name, kind, &file_path, point, &references);
-                    }
+                Ok(ScannedFile::Unchanged(file_path, stamp)) => {
+                    self.index.set_file_stamp(&file_path, stamp);
+                    scanned_files.insert(file_path);
+                }
+                Ok(ScannedFile::Changed(file_path, stamp, parsed)) => {
+                    scanned_files.insert(file_path.clone());
+                    changed_files.push((file_path, stamp, parsed));
                 }
                 Err(e) => eprintln!("Could not process file: {e}"),
             }
         }
 
-        // FIXME get these stats from the Index
-        println!("Searching {declaration_count} declarations and {references_count} references.");
+        // Previously scanned files under the scanned path which are gone, compared by
+        // path components so that ./SourcesOld isn't under ./Sources
+        let root = normalize_path(Path::new(path));
+        let removed_files: Vec<_> = previous_files
+            .iter()
+            .filter(|(normalized, (file, _))| {
+                normalized.starts_with(&root) && !scanned_files.contains(file)
+            })
+            .map(|(_, (file, _))| file.as_str())
+            .collect();
 
-        Ok(())
+        let stale_files: Vec<_> = changed_files
+            .iter()
+            .map(|(file_path, _, _)| file_path.as_str())
+            .chain(removed_files.iter().copied())
+            .collect();
+
+        self.index.remove_files(&stale_files);
+
+        let changed_count = changed_files.len();
+        let mut declaration_count = 0;
+        let mut references_count = 0;
+
+        for (file_path, stamp, parsed) in changed_files {
+            self.index
+                .set_file_module(&file_path, parsed.module.as_deref());
+
+            let imports: Vec<_> = parsed.imports.iter().map(String::as_str).collect();
+            self.index.set_file_imports(&file_path, &imports);
+
+            let errors = parsed
+                .errors
+                .into_iter()
+                .map(|error| SyntaxError {
+                    start: error.start,
+                    end: error.end,
+                    missing: error.missing,
+                })
+                .collect();
+            self.index.set_file_errors(&file_path, errors);
+
+            for declaration in parsed.declarations {
+                declaration_count += 1;
+
+                let (kind, name) = definition_parts(declaration.definition);
+                let kind = declaration_kind(kind);
+                let point = declaration.location;
+                let references: Vec<_> = declaration
+                    .references
+                    .iter()
+                    .map(|r| {
+                        references_count += 1;
+
+                        (
+                            r.name.as_str(),
+                            &r.location,
+                            reference_kind(r.kind),
+                            confidence(r.confidence),
+                        )
+                    })
+                    .collect();
+
+                self.index
+                    .add_declaration(&name, kind, &file_path, point, &references);
+            }
+
+            self.index.set_file_stamp(&file_path, stamp);
+        }
+
+        self.index.set_queries_hash(queries_hash);
+        self.index.resolve_references();
+
+        Ok(ScanStats {
+            files: scanned_files.len(),
+            changed: changed_count,
+            removed: removed_files.len(),
+            declarations: declaration_count,
+            references: references_count,
+        })
+    }
+
+    // Lists the files which failed to parse, with the location of their first error
+    fn print_syntax_error_summary(&self) {
+        let files = self.index.syntax_errors();
+
+        if files.is_empty() {
+            return;
+        }
+
+        eprintln!(
+            "{} files have syntax errors, their declarations may be incomplete:",
+            files.len()
+        );
+
+        for (path, errors) in files {
+            let first = &errors[0];
+
+            eprintln!(
+                "- {}:{}:{} ({} errors)",
+                path,
+                first.start.row + 1,
+                first.start.column + 1,
+                errors.len()
+            );
+        }
+
+        eprintln!("Run `drake diagnostics` for details.");
+    }
+
+    /// Print the syntax errors found in the scanned files
+    pub fn print_diagnostics(&self) {
+        let files = self.index.syntax_errors();
+
+        for (path, errors) in &files {
+            for error in *errors {
+                let message = match &error.missing {
+                    Some(kind) => format!("missing {}", kind),
+                    None => format!(
+                        "syntax error up to {}:{}",
+                        error.end.row + 1,
+                        error.end.column + 1
+                    ),
+                };
+
+                println!(
+                    "{}:{}:{}: {}",
+                    path,
+                    error.start.row + 1,
+                    error.start.column + 1,
+                    message
+                );
+            }
+        }
+
+        if files.is_empty() {
+            println!("No syntax errors found.");
+        }
     }
 
-    // TODO reuse later?
-    pub fn package_name(&mut self, path: &str) -> anyhow::Result<()> {
-        let mut builder = TypesBuilder::new();
-        builder
-            .add_defaults()
-            .add("swiftpackage", "Package.swift")?;
+    // Warns about references to names declared in several imported modules, which are
+    // left unresolved
+    fn print_ambiguous_references(&self) {
+        for reference in self.index.ambiguous_references() {
+            let t = self
+                .index
+                .get_type(reference.from)
+                .expect("index refers to an unknown type");
+            let path = self
+                .index
+                .file_path(&t.declarations[reference.declaration])
+                .expect("index refers to an unknown file");
+            let candidates: Vec<_> = reference
+                .candidates
+                .iter()
+                .map(|&type_id| {
+                    self.index
+                        .get_type(type_id)
+                        .expect("index refers to an unknown type")
+                        .qualified_name()
+                })
+                .collect();
 
-        let matcher = builder.select("swiftpackage").build()?;
-        let walk = WalkBuilder::new(path).types(matcher).build_parallel();
+            eprintln!(
+                "{}:{}:{}: warning: reference in {} is ambiguous, it could be {}",
+                path,
+                reference.point.row + 1,
+                reference.point.column + 1,
+                t.name,
+                candidates.join(" or ")
+            );
+        }
+    }
+
+    // Adds the packages found in the path to the index
+    fn scan_packages(&mut self, path: &str, parsers: Arc<Vec<Parser>>) -> anyhow::Result<()> {
+        let walk = walk_files(path, |language| language.manifest_globs())?;
 
-        let packages = worker_pool::process_files(walk, move |path, parser| {
+        let packages = worker_pool::process_files(walk, parsers, move |path, parser| {
             let source = fs::read_to_string(path)?;
             let tree = parser.parse(source)?;
-            let name = tree.package_name()?;
 
-            Ok(Package {
-                name: name.to_string(),
+            let Some(manifest) = tree.manifest(path)? else {
+                return Ok(None);
+            };
+
+            Ok(Some(Package {
+                manifest,
                 prefix: path
                     .parent()
                     .ok_or_else(|| anyhow!("Package manifest has no parent directory??"))?
                     .to_owned(),
-            })
+            }))
         });
 
+        let mut package_count = 0;
+
         for package in packages {
             match package {
-                Ok(package) => self
-                    .index
-                    .add_package(&package.name, &package.prefix.to_string_lossy()),
+                Ok(Some(package)) => {
+                    package_count += 1;
+
+                    self.add_manifest(package);
+                }
+                Ok(None) => (),
                 Err(e) => eprintln!("Could not process file: {e}"),
             }
         }
 
-        println!("Done. Index: {:#?}", self.index);
+        eprintln!("Found {package_count} packages.");
 
         Ok(())
     }
+
+    fn add_manifest(&mut self, package: Package) {
+        let manifest = package.manifest;
+        let package_id = self
+            .index
+            .add_package(&manifest.name, &package.prefix.to_string_lossy());
+
+        for product in &manifest.products {
+            let targets: Vec<_> = product.targets.iter().map(String::as_str).collect();
+
+            self.index.add_product(package_id, &product.name, &targets);
+        }
+
+        for dependency in &manifest.dependencies {
+            self.index.add_package_dependency(
+                package_id,
+                &dependency.name,
+                dependency.path.as_deref(),
+            );
+        }
+
+        for target in &manifest.targets {
+            let kind = match target.kind.as_str() {
+                "target" => TargetKind::Regular,
+                "executableTarget" => TargetKind::Executable,
+                "testTarget" => TargetKind::Test,
+                "macro" => TargetKind::Macro,
+                "plugin" => TargetKind::Plugin,
+                // Binary and system library targets have no sources
+                _ => continue,
+            };
+            let dependencies: Vec<_> = target
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.name.as_str(), dependency.package.as_deref()))
+                .collect();
+
+            self.index.add_target(
+                package_id,
+                &target.name,
+                kind,
+                target.path.as_deref(),
+                &dependencies,
+            );
+        }
+    }
+}
+
+// Walks the files matching the globs given for each of the indexed languages
+fn walk_files(
+    path: &str,
+    globs: impl Fn(&dyn Language) -> &'static [&'static str],
+) -> anyhow::Result<WalkParallel> {
+    let mut builder = TypesBuilder::new();
+
+    for &language in LANGUAGES {
+        let globs = globs(language);
+
+        for glob in globs {
+            builder.add(language.name(), glob)?;
+        }
+
+        if !globs.is_empty() {
+            builder.select(language.name());
+        }
+    }
+
+    Ok(WalkBuilder::new(path)
+        .types(builder.build()?)
+        .build_parallel())
+}
+
+// Parsers of the indexed languages, with the query files configured in .drake.toml in
+// the scanned path. Invalid query files fail before any file is parsed.
+fn parsers(path: &str) -> anyhow::Result<Arc<Vec<Parser>>> {
+    let config_path = Path::new(path).join(CONFIG_FILE);
+    let config = if config_path.is_file() {
+        Config::load(&config_path)?
+    } else {
+        Config::default()
+    };
+
+    for name in config.queries.keys() {
+        if !LANGUAGES.iter().any(|language| language.name() == name) {
+            let names: Vec<_> = LANGUAGES.iter().map(|language| language.name()).collect();
+
+            bail!(
+                "Queries are configured for an unknown language {}, expected one of {}",
+                name,
+                names.join(", ")
+            );
+        }
+    }
+
+    let query_file = |file: &Option<String>, replace: bool| -> anyhow::Result<_> {
+        let Some(file) = file else {
+            return Ok(None);
+        };
+        let source = fs::read_to_string(Path::new(path).join(file))
+            .with_context(|| format!("Could not read query file {}", file))?;
+
+        Ok(Some(QueryFile {
+            path: file.clone(),
+            source,
+            replace,
+        }))
+    };
+
+    let parsers = LANGUAGES
+        .iter()
+        .map(|&language| {
+            let custom = match config.queries.get(language.name()) {
+                Some(queries) => CustomQueries {
+                    declarations: query_file(&queries.declarations, queries.replace)?,
+                    references: query_file(&queries.references, queries.replace)?,
+                },
+                None => CustomQueries::default(),
+            };
+
+            Parser::new(language, &custom)
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(Arc::new(parsers))
+}
+
+// Result of scanning a single file
+enum ScannedFile {
+    Unchanged(String, FileStamp),
+    Changed(String, FileStamp, ParsedFile),
+}
+
+// Contents of a changed file
+struct ParsedFile {
+    module: Option<String>,
+    imports: Vec<String>,
+    declarations: Vec<parser::Declaration>,
+    errors: Vec<parser::SyntaxError>,
+}
+
+// Numbers of files and declarations seen by a scan
+#[derive(Debug, PartialEq, Eq)]
+struct ScanStats {
+    files: usize,
+    changed: usize,
+    removed: usize,
+    declarations: usize,
+    references: usize,
+}
+
+// Absolute path without `.` components, so that ./Sources/Model.swift and the absolute
+// path of the same file compare equal
+fn normalize_path(path: &Path) -> PathBuf {
+    let path = path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+// FNV-1a, which unlike the std hasher is stable across Rust versions
+fn content_hash(source: &str) -> u64 {
+    source.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn file_entry(
+    path: &str,
+    tree: Tree,
+    decl: bool,
+    refs: bool,
+    full: bool,
+) -> anyhow::Result<json::File> {
+    let declarations = if decl {
+        let declarations = tree
+            .declarations()?
+            .into_iter()
+            .map(|declaration| {
+                let (kind, name) = definition_parts(declaration.definition);
+
+                let references = refs.then(|| {
+                    declaration
+                        .references
+                        .into_iter()
+                        .map(|reference| json::FileReference {
+                            name: reference.name,
+                            location: reference.location.into(),
+                            kind: reference_kind(reference.kind),
+                            confidence: confidence(reference.confidence),
+                        })
+                        .collect()
+                });
+
+                json::FileDeclaration {
+                    kind,
+                    name,
+                    location: declaration.location.into(),
+                    references,
+                }
+            })
+            .collect();
+
+        Some(declarations)
+    } else {
+        None
+    };
+
+    Ok(json::File {
+        path: path.to_string(),
+        declarations,
+        tree: full.then(|| tree.to_string()),
+    })
+}
+
+// Kind of a parsed declaration, as named in the JSON output, and its name
+fn definition_parts(definition: Definition) -> (&'static str, String) {
+    match definition {
+        Definition::Class { kind, name } => (kind, name),
+        Definition::Protocol { name } => ("protocol", name),
+        Definition::Extension { name } => ("extension", name),
+        Definition::TypeAlias { name } => ("typealias", name),
+        Definition::AssociatedType { name } => ("associatedtype", name),
+        Definition::Function { name } => ("function", name),
+        Definition::Variable { kind: "let", name } => ("constant", name),
+        Definition::Variable { name, .. } => ("variable", name),
+        Definition::Macro { name } => ("macro", name),
+    }
+}
+
+fn declaration_kind(kind: &str) -> Kind {
+    match kind {
+        "class" => Kind::Class,
+        "struct" => Kind::Struct,
+        "enum" => Kind::Enum,
+        "actor" => Kind::Actor,
+        "protocol" => Kind::Protocol,
+        "extension" => Kind::Extension,
+        "typealias" => Kind::TypeAlias,
+        "associatedtype" => Kind::AssociatedType,
+        "function" => Kind::Function,
+        "variable" => Kind::Variable,
+        "constant" => Kind::Constant,
+        "macro" => Kind::Macro,
+        x => unreachable!("Unknown declaration kind {x}"),
+    }
+}
+
+fn kind_description(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Struct => "struct declared",
+        Kind::Enum => "enum declared",
+        Kind::Class => "class declared",
+        Kind::Actor => "actor declared",
+        Kind::Protocol => "protocol declared",
+        Kind::Extension => "extended",
+        Kind::TypeAlias => "typealias declared",
+        Kind::AssociatedType => "associated type declared",
+        Kind::Function => "function declared",
+        Kind::Variable => "variable declared",
+        Kind::Constant => "constant declared",
+        Kind::Macro => "macro declared",
+    }
+}
+
+fn reference_kind(kind: &str) -> ReferenceKind {
+    match kind {
+        "inheritance" => ReferenceKind::Inheritance,
+        "conformance" => ReferenceKind::Conformance,
+        "property" => ReferenceKind::StoredProperty,
+        "signature" => ReferenceKind::FunctionSignature,
+        "generic" => ReferenceKind::GenericConstraint,
+        "alias" => ReferenceKind::Alias,
+        "body" => ReferenceKind::BodyCall,
+        x => unreachable!("Unknown reference kind {x}"),
+    }
+}
+
+fn confidence(confidence: &str) -> Confidence {
+    match confidence {
+        "high" => Confidence::High,
+        "medium" => Confidence::Medium,
+        x => unreachable!("Unknown reference confidence {x}"),
+    }
+}
+
+fn reference_kind_description(kind: ReferenceKind) -> &'static str {
+    match kind {
+        ReferenceKind::Inheritance => "superclass",
+        ReferenceKind::Conformance => "conformance",
+        ReferenceKind::StoredProperty => "stored property",
+        ReferenceKind::FunctionSignature => "function signature",
+        ReferenceKind::GenericConstraint => "generic constraint",
+        ReferenceKind::Alias => "aliased type",
+        ReferenceKind::BodyCall => "body call",
+    }
 }
 
 // TODO improve this
@@ -240,26 +1274,12 @@ fn print(path: &str, tree: Tree, decl: bool, refs: bool, full: bool) -> anyhow::
         for declaration in tree.declarations()? {
             let loc = declaration.location;
 
-            match declaration.definition {
-                Definition::Class { kind, name } => {
-                    out.push_str(&format!(
-                        "\n{} {} at {}:{}\n",
-                        kind, name, loc.row, loc.column
-                    ));
-                }
-                Definition::Protocol { name } => {
-                    out.push_str(&format!(
-                        "\nprotocol {} at {}:{}\n",
-                        name, loc.row, loc.column
-                    ));
-                }
-                Definition::Extension { name } => {
-                    out.push_str(&format!(
-                        "\nextension {} at {}:{}\n",
-                        name, loc.row, loc.column
-                    ));
-                }
-            }
+            let (kind, name) = definition_parts(declaration.definition);
+
+            out.push_str(&format!(
+                "\n{} {} at {}:{}\n",
+                kind, name, loc.row, loc.column
+            ));
 
             if !refs {
                 continue;
@@ -269,8 +1289,12 @@ fn print(path: &str, tree: Tree, decl: bool, refs: bool, full: bool) -> anyhow::
                 let loc = reference.location;
 
                 out.push_str(&format!(
-                    "- {} at {}:{}\n",
-                    reference.name, loc.row, loc.column
+                    "- {} at {}:{}, {}, {} confidence\n",
+                    reference.name,
+                    loc.row,
+                    loc.column,
+                    reference_kind_description(reference_kind(reference.kind)),
+                    reference.confidence
                 ));
             }
         }
@@ -283,3 +1307,176 @@ fn print(path: &str, tree: Tree, decl: bool, refs: bool, full: bool) -> anyhow::
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn origin(drake: &Drake, name: &str) -> TypeOrigin {
+        let type_id = drake.index.type_id(name).unwrap();
+
+        drake.index.get_type(type_id).unwrap().origin()
+    }
+
+    #[test]
+    fn rescans_only_changed_and_removed_files() {
+        let dir = env::temp_dir().join(format!("drake-rescan-{}", process::id()));
+        let index_file = dir.with_extension("json");
+        let path = dir.to_string_lossy().to_string();
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Money.swift"), "struct Money {}\n").unwrap();
+        fs::write(dir.join("Cart.swift"), "struct Cart {}\n").unwrap();
+        fs::write(
+            dir.join("Order.swift"),
+            "struct Order {\n    let total: Money\n}\n",
+        )
+        .unwrap();
+
+        let mut drake = Drake::new();
+        let stats = drake.scan_files(&path).unwrap();
+        drake.save_index(&index_file).unwrap();
+
+        assert_eq!(
+            stats,
+            ScanStats {
+                files: 3,
+                changed: 3,
+                removed: 0,
+                declarations: 3,
+                references: 1,
+            }
+        );
+
+        // Money is written again with the same contents
+        fs::write(dir.join("Money.swift"), "struct Money {}\n").unwrap();
+        fs::write(
+            dir.join("Order.swift"),
+            "struct Order {\n    let total: Money\n    let cart: Cart\n}\n",
+        )
+        .unwrap();
+        fs::remove_file(dir.join("Cart.swift")).unwrap();
+
+        let mut drake = Drake::new();
+        drake.load_index(&index_file).unwrap();
+        let stats = drake.scan_files(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_file(&index_file).unwrap();
+
+        assert_eq!(
+            stats,
+            ScanStats {
+                files: 2,
+                changed: 1,
+                removed: 1,
+                declarations: 1,
+                references: 2,
+            }
+        );
+        assert_eq!(origin(&drake, "Money"), TypeOrigin::Local);
+        assert_eq!(origin(&drake, "Order"), TypeOrigin::Local);
+        assert_eq!(origin(&drake, "Cart"), TypeOrigin::External);
+    }
+
+    #[test]
+    fn rescans_only_files_under_the_scanned_path() {
+        let dir = env::temp_dir().join(format!("drake-subpath-{}", process::id()));
+        let path = dir.to_string_lossy().to_string();
+
+        fs::create_dir_all(dir.join("Sources")).unwrap();
+        fs::create_dir_all(dir.join("SourcesOld")).unwrap();
+        fs::write(dir.join("Sources/Money.swift"), "struct Money {}\n").unwrap();
+        fs::write(dir.join("SourcesOld/Cart.swift"), "struct Cart {}\n").unwrap();
+
+        let mut drake = Drake::new();
+        drake.scan_files(&path).unwrap();
+
+        // A directory next to one sharing its name's prefix, then spelled differently
+        let stats = [
+            drake.scan_files(&format!("{}/Sources", path)).unwrap(),
+            drake.scan_files(&format!("{}/./Sources", path)).unwrap(),
+        ];
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        for stats in stats {
+            assert_eq!(
+                stats,
+                ScanStats {
+                    files: 1,
+                    changed: 0,
+                    removed: 0,
+                    declarations: 0,
+                    references: 0,
+                }
+            );
+        }
+        assert_eq!(origin(&drake, "Cart"), TypeOrigin::Local);
+    }
+
+    #[test]
+    fn normalizes_relative_and_absolute_paths() {
+        let absolute = env::current_dir().unwrap().join("Sources/Model.swift");
+
+        assert_eq!(
+            normalize_path(Path::new("./Sources/Model.swift")),
+            normalize_path(&absolute)
+        );
+        assert_eq!(normalize_path(Path::new("Sources/./Model.swift")), absolute);
+        assert!(!normalize_path(Path::new("./SourcesOld/Cart.swift"))
+            .starts_with(normalize_path(Path::new("./Sources"))));
+    }
+
+    #[test]
+    fn rescans_every_file_when_the_queries_change() {
+        let dir = env::temp_dir().join(format!("drake-requery-{}", process::id()));
+        let index_file = dir.with_extension("json");
+        let path = dir.to_string_lossy().to_string();
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Money.swift"), "struct Money {}\n").unwrap();
+        fs::write(
+            dir.join("App.swift"),
+            "func start() {\n    container.register(Money.self)\n}\n",
+        )
+        .unwrap();
+
+        let mut drake = Drake::new();
+        drake.scan_files(&path).unwrap();
+        drake.save_index(&index_file).unwrap();
+
+        // A query file matching registered types is added after the first scan
+        fs::write(
+            dir.join(CONFIG_FILE),
+            "[queries.swift]\nreferences = \"references.scm\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("references.scm"),
+            "((call_expression\n   (navigation_expression (navigation_suffix (simple_identifier) @_method))\n   (call_suffix (value_arguments (value_argument\n     (navigation_expression (simple_identifier) @name)))))\n (#eq? @_method \"register\"))\n",
+        )
+        .unwrap();
+
+        let mut drake = Drake::new();
+        drake.load_index(&index_file).unwrap();
+        let stats = drake.scan_files(&path).unwrap();
+        drake.save_index(&index_file).unwrap();
+
+        assert_eq!((stats.changed, stats.references), (2, 1));
+
+        // The same queries find nothing changed
+        let mut drake = Drake::new();
+        drake.load_index(&index_file).unwrap();
+        let stats = drake.scan_files(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_file(&index_file).unwrap();
+
+        assert_eq!(stats.changed, 0);
+    }
+}
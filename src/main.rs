@@ -1,6 +1,12 @@
+use std::path::PathBuf;
+
+use anyhow::bail;
 use clap::{Parser, Subcommand};
 
-use drake::Drake;
+use drake::{Cluster, Drake, Format, GraphFormat, PackageGraphFormat, ReferenceKind};
+
+// Index file written by `scan --save` without a file name
+const DEFAULT_INDEX_FILE: &str = ".drake-index.json";
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -18,9 +24,158 @@ enum Command {
         /// Path to scan
         #[arg(default_value = ".")]
         path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
         /// Include all type dependencies, including ones declared outside the codebase
         #[arg(long = "all")]
         all: bool,
+        /// Only follow declarations made in this package
+        #[arg(long = "package", value_name = "NAME")]
+        package: Option<String>,
+        /// Only follow references of these kinds
+        #[arg(long = "kind", value_enum, value_delimiter = ',')]
+        kinds: Vec<ReferenceKind>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Scan a path and list all declarations which depend on a type, directly or transitively
+    Dependents {
+        /// Type name to find dependents of
+        type_name: String,
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// Only list declarations made in this package
+        #[arg(long = "package", value_name = "NAME")]
+        package: Option<String>,
+        /// Only follow references of these kinds
+        #[arg(long = "kind", value_enum, value_delimiter = ',')]
+        kinds: Vec<ReferenceKind>,
+    },
+    /// Scan a path and list the superclasses and protocols of a type, or its subtypes
+    Hierarchy {
+        /// Type name to list the hierarchy of
+        type_name: String,
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// List subclasses and conforming types instead, including conformances in extensions
+        #[arg(long)]
+        down: bool,
+    },
+    /// Scan a path and export the dependency graph as Graphviz DOT or Mermaid
+    Export {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// Only export types reachable from this type
+        #[arg(long = "from")]
+        type_name: Option<String>,
+        /// Include types declared outside the codebase
+        #[arg(long = "all")]
+        all: bool,
+        /// Graph format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Group types by the file or package they are declared in
+        #[arg(long, value_enum)]
+        cluster: Option<Cluster>,
+    },
+    /// Scan a path and query the packages in it: Swift packages, Gradle builds and Cargo
+    /// packages
+    Packages {
+        #[command(subcommand)]
+        command: PackagesCommand,
+    },
+    /// Scan a path and compare the target dependencies declared in package manifests with
+    /// the references between the targets' types
+    CheckManifest {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+    },
+    /// Scan a path and check references between types against the layers in .drake.toml,
+    /// exiting with an error on violations
+    Check {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// Config file defining the layers, .drake.toml in the scanned path by default
+        #[arg(long = "config", value_name = "FILE")]
+        config: Option<PathBuf>,
+    },
+    /// Scan a path and list groups of types which depend on each other
+    Cycles {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// Only list cycles between types declared in different packages, types outside
+        /// of any package are left out
+        #[arg(long = "cross-package")]
+        cross_package: bool,
+    },
+    /// Scan a path and find the shortest chain of dependencies from one type to another
+    Path {
+        /// Type name to start from
+        from: String,
+        /// Type name to find
+        to: String,
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// List up to N chains which don't visit any type twice, instead of only the shortest
+        #[arg(long = "paths", value_name = "N")]
+        paths: Option<usize>,
+    },
+    /// Scan a path and list the syntax errors tree-sitter found in the source files, which
+    /// may cause missing declarations and references
+    Diagnostics {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+    },
+    /// Scan a path and optionally save the index for later queries
+    Scan {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Start from an index saved by `scan --save`, only parsing files which changed since
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// Save the index to a file, to be loaded by queries with --index
+        #[arg(
+            long,
+            value_name = "FILE",
+            num_args = 0..=1,
+            default_missing_value = DEFAULT_INDEX_FILE
+        )]
+        save: Option<PathBuf>,
     },
     /// Print contents of specific files
     Print {
@@ -33,6 +188,25 @@ enum Command {
         references: bool,
         #[arg(long)]
         full: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+#[derive(Subcommand)]
+enum PackagesCommand {
+    /// Print the dependencies between packages, aggregated from references between their types
+    Graph {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: String,
+        /// Load an index saved by `scan --save` instead of scanning the path
+        #[arg(long = "index", value_name = "FILE")]
+        index: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = PackageGraphFormat::Text)]
+        format: PackageGraphFormat,
     },
 }
 
@@ -44,19 +218,125 @@ fn main() -> anyhow::Result<()> {
     match &cli.command {
         Command::Deps {
             path,
+            index,
+            type_name,
+            all,
+            package,
+            kinds,
+            format,
+        } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_dependencies(type_name, *all, package.as_deref(), kinds, *format)?;
+        }
+        Command::Dependents {
+            path,
+            index,
+            type_name,
+            package,
+            kinds,
+        } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_dependents(type_name, package.as_deref(), kinds)?;
+        }
+        Command::Hierarchy {
+            type_name,
+            path,
+            index,
+            down,
+        } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_hierarchy(type_name, *down)?;
+        }
+        Command::Export {
+            path,
+            index,
             type_name,
             all,
+            format,
+            cluster,
+        } => {
+            build_index(&mut drake, path, index)?;
+
+            drake.export(type_name.as_deref(), *all, *format, *cluster)?;
+        }
+        Command::Packages {
+            command:
+                PackagesCommand::Graph {
+                    path,
+                    index,
+                    format,
+                },
+        } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_package_graph(*format)?;
+        }
+        Command::CheckManifest { path, index } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_manifest_check()?;
+        }
+        Command::Check {
+            path,
+            index,
+            config,
+        } => {
+            build_index(&mut drake, path, index)?;
+
+            let violations = drake.check_layers(path, config.as_deref())?;
+
+            if violations > 0 {
+                bail!("Found {} layering violations.", violations);
+            }
+        }
+        Command::Cycles {
+            path,
+            index,
+            cross_package,
         } => {
+            build_index(&mut drake, path, index)?;
+
+            drake.print_cycles(*cross_package)?;
+        }
+        Command::Path {
+            from,
+            to,
+            path,
+            index,
+            paths,
+        } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_paths(from, to, *paths)?;
+        }
+        Command::Diagnostics { path, index } => {
+            build_index(&mut drake, path, index)?;
+            drake.print_diagnostics();
+        }
+        Command::Scan { path, index, save } => {
+            if let Some(file) = index {
+                drake.load_index(file)?;
+            }
+
             drake.scan(path)?;
-            drake.print_dependencies(type_name, *all)?;
+
+            if let Some(file) = save {
+                drake.save_index(file)?;
+            }
         }
         Command::Print {
             path,
             declarations,
             references,
             full,
-        } => drake.print(path, *declarations, *references, *full)?,
+            format,
+        } => drake.print(path, *declarations, *references, *full, *format)?,
     }
 
     Ok(())
 }
+
+// Loads a saved index if one was given, otherwise scans the path
+fn build_index(drake: &mut Drake, path: &str, index: &Option<PathBuf>) -> anyhow::Result<()> {
+    match index {
+        Some(file) => drake.load_index(file),
+        None => drake.scan(path),
+    }
+}
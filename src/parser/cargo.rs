@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use toml::Value;
+
+use super::tree::{Manifest, PackageDependency, Product, Target, TargetDependency};
+
+#[derive(Debug, Deserialize)]
+struct CargoManifest {
+    // Missing in workspace roots
+    package: Option<CargoPackage>,
+    lib: Option<CargoLib>,
+    // Versions, or tables with e.g. a path or the name of a renamed package
+    #[serde(default)]
+    dependencies: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoPackage {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoLib {
+    name: Option<String>,
+    path: Option<String>,
+}
+
+/// A Cargo package, with its crate as the only target, in the directory of the crate
+/// root, e.g. src. The crate depends on the crates of the package's dependencies,
+/// which are each a product of their package. Workspace roots describe no package,
+/// the members are read from their own manifests.
+pub fn manifest(source: &str) -> anyhow::Result<Option<Manifest>> {
+    let manifest: CargoManifest = toml::from_str(source)?;
+
+    let Some(package) = manifest.package else {
+        return Ok(None);
+    };
+
+    let crate_name = manifest
+        .lib
+        .as_ref()
+        .and_then(|lib| lib.name.clone())
+        .unwrap_or_else(|| crate_name(&package.name));
+    let crate_path = manifest
+        .lib
+        .as_ref()
+        .and_then(|lib| lib.path.as_deref())
+        .and_then(|path| path.rsplit_once('/'))
+        .map_or("src", |(directory, _)| directory);
+
+    let dependencies: Vec<_> = manifest
+        .dependencies
+        .iter()
+        .map(|(name, dependency)| PackageDependency {
+            name: dependency
+                .get("package")
+                .and_then(Value::as_str)
+                .unwrap_or(name)
+                .to_string(),
+            path: dependency
+                .get("path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        })
+        .collect();
+
+    let target = Target {
+        kind: "target".to_string(),
+        name: crate_name.clone(),
+        path: Some(crate_path.to_string()),
+        dependencies: dependencies
+            .iter()
+            .map(|dependency| TargetDependency {
+                name: dependency.name.clone(),
+                package: Some(dependency.name.clone()),
+            })
+            .collect(),
+    };
+
+    Ok(Some(Manifest {
+        products: vec![Product {
+            name: package.name.clone(),
+            targets: vec![crate_name],
+        }],
+        name: package.name,
+        dependencies,
+        targets: vec![target],
+    }))
+}
+
+// Name of the crate of a package, which can't contain dashes
+fn crate_name(package: &str) -> String {
+    package.replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn reads_the_package_crate_and_dependencies() {
+        let source = r#"
+            [package]
+            name = "drake-core"
+            version = "0.1.0"
+
+            [lib]
+            path = "lib/core.rs"
+
+            [dependencies]
+            anyhow = "1.0"
+            index = { path = "../index" }
+            parser = { package = "drake-parser", workspace = true }
+
+            [dev-dependencies]
+            pretty_assertions = "1.4.0"
+        "#;
+
+        let manifest = manifest(source).unwrap().unwrap();
+        let target = &manifest.targets[0];
+
+        assert_eq!(manifest.name, "drake-core");
+        assert_eq!(manifest.products[0].targets, vec!["drake_core".to_string()]);
+        assert_eq!(
+            (target.name.as_str(), target.path.as_deref()),
+            ("drake_core", Some("lib"))
+        );
+        assert_eq!(
+            manifest
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.name.as_str(), dependency.path.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("anyhow", None),
+                ("index", Some("../index")),
+                ("drake-parser", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn describes_no_package_for_workspace_roots() {
+        let source = r#"
+            [workspace]
+            members = ["crates/*"]
+        "#;
+
+        assert!(manifest(source).unwrap().is_none());
+    }
+}
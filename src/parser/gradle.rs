@@ -0,0 +1,193 @@
+use std::{fs, path::Path};
+
+use anyhow::anyhow;
+
+use super::tree::{Manifest, Target, TargetDependency};
+
+// Build files of a module, tried in order
+const BUILD_FILES: &[&str] = &["build.gradle.kts", "build.gradle"];
+
+/// A Gradle build as a package. The settings file names the build and includes its
+/// modules, which become targets depending on the modules their build files refer to
+/// with `project(":name")`. Both the Kotlin and the Groovy DSL are read as plain text.
+pub fn manifest(settings_path: &Path, settings: &str) -> anyhow::Result<Manifest> {
+    let directory = settings_path
+        .parent()
+        .ok_or_else(|| anyhow!("Gradle settings have no parent directory"))?;
+    let (name, modules) = parse_settings(settings);
+
+    let name = name
+        .or_else(|| {
+            directory
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .ok_or_else(|| anyhow!("Could not tell the name of the Gradle build"))?;
+
+    let targets = modules
+        .iter()
+        .map(|module| {
+            let path = module_path(module);
+            let build = BUILD_FILES
+                .iter()
+                .find_map(|file| fs::read_to_string(directory.join(&path).join(file)).ok())
+                .unwrap_or_default();
+
+            Target {
+                kind: "target".to_string(),
+                name: module_name(module),
+                path: Some(path),
+                dependencies: project_dependencies(&build)
+                    .iter()
+                    .map(|dependency| TargetDependency {
+                        name: module_name(dependency),
+                        package: None,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Ok(Manifest {
+        name,
+        products: vec![],
+        dependencies: vec![],
+        targets,
+    })
+}
+
+// Root project name and the paths of the included modules, e.g. ":core:network"
+fn parse_settings(source: &str) -> (Option<String>, Vec<String>) {
+    let mut name = None;
+    let mut modules = vec![];
+    // Inside the parentheses of an include spanning lines
+    let mut in_include = false;
+
+    for line in source.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+
+        if in_include {
+            modules.extend(strings(line));
+            in_include = !line.contains(')');
+
+            continue;
+        }
+
+        if let Some(arguments) = line.strip_prefix("include") {
+            if arguments.starts_with(|c: char| c == '(' || c.is_whitespace()) {
+                modules.extend(strings(arguments));
+                in_include = arguments.trim_start().starts_with('(') && !arguments.contains(')');
+            }
+        } else if line.starts_with("rootProject.name") {
+            name = strings(line).into_iter().next();
+        }
+    }
+
+    (name, modules)
+}
+
+// Paths of the modules a build file depends on with project(...)
+fn project_dependencies(source: &str) -> Vec<String> {
+    let mut dependencies = vec![];
+
+    for (idx, _) in source.match_indices("project(") {
+        let arguments = &source[idx..];
+        let arguments = &arguments[..arguments.find(')').unwrap_or(arguments.len())];
+
+        if let Some(path) = strings(arguments).into_iter().next() {
+            if !dependencies.contains(&path) {
+                dependencies.push(path);
+            }
+        }
+    }
+
+    dependencies
+}
+
+// Contents of the single or double quoted string literals in a piece of code
+fn strings(code: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let mut rest = code;
+
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().expect("found a quote");
+        let after = &rest[start + 1..];
+
+        let Some(end) = after.find(quote) else {
+            break;
+        };
+
+        strings.push(after[..end].to_string());
+        rest = &after[end + 1..];
+    }
+
+    strings
+}
+
+// Target name of a module, its path without the leading colon, e.g. core:network
+fn module_name(module: &str) -> String {
+    module.trim_start_matches(':').to_string()
+}
+
+// Directory of a module relative to the settings file, e.g. core/network
+fn module_path(module: &str) -> String {
+    module_name(module).replace(':', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn reads_included_modules_in_both_dsls() {
+        let kotlin = r#"
+            rootProject.name = "Shop"
+
+            include(":app")
+            include(
+                ":core:network", // shared
+                ":core:model",
+            )
+        "#;
+        let groovy = "rootProject.name = 'Shop'\ninclude ':app', ':core:network'\n";
+
+        assert_eq!(
+            parse_settings(kotlin),
+            (
+                Some("Shop".to_string()),
+                vec![
+                    ":app".to_string(),
+                    ":core:network".to_string(),
+                    ":core:model".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            parse_settings(groovy),
+            (
+                Some("Shop".to_string()),
+                vec![":app".to_string(), ":core:network".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn reads_project_dependencies() {
+        let build = r#"
+            dependencies {
+                implementation(project(":core:network"))
+                implementation project(path: ':core:model')
+                testImplementation(project(":core:network"))
+                implementation("com.squareup.okhttp3:okhttp:4.12.0")
+            }
+        "#;
+
+        assert_eq!(
+            project_dependencies(build),
+            vec![":core:network".to_string(), ":core:model".to_string()]
+        );
+        assert_eq!(module_path(":core:network"), "core/network");
+    }
+}
@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use anyhow::bail;
+use tree_sitter::Node;
+
+use super::{gradle, Definition, Language, Manifest, Tree};
+
+/// Kotlin sources and Gradle builds
+pub struct Kotlin;
+
+impl Language for Kotlin {
+    fn name(&self) -> &'static str {
+        "kotlin"
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        tree_sitter_kotlin::language()
+    }
+
+    fn file_globs(&self) -> &'static [&'static str] {
+        &["*.kt"]
+    }
+
+    fn manifest_globs(&self) -> &'static [&'static str] {
+        &["settings.gradle", "settings.gradle.kts"]
+    }
+
+    fn declarations_query(&self) -> &'static str {
+        include_str!("kotlin/declarations.scm")
+    }
+
+    fn references_query(&self) -> &'static str {
+        include_str!("kotlin/references.scm")
+    }
+
+    // Kotlin imports name packages and classes, e.g. com.example.core.Router, rather
+    // than Gradle modules
+    fn imports_query(&self) -> &'static str {
+        include_str!("kotlin/imports.scm")
+    }
+
+    fn manifest(&self, tree: &Tree, path: &Path) -> anyhow::Result<Option<Manifest>> {
+        gradle::manifest(path, &tree.source).map(Some)
+    }
+
+    fn definition(
+        &self,
+        pattern_index: usize,
+        declaration: Node,
+        kind: Option<&'static str>,
+        name: String,
+    ) -> anyhow::Result<Definition> {
+        let definition = match (pattern_index, kind) {
+            (0, Some("interface")) => Definition::Protocol { name },
+            (0, _) if child(declaration, "enum_class_body").is_some() => {
+                Definition::Class { kind: "enum", name }
+            }
+            // Objects are singleton classes
+            (0 | 1, _) => Definition::Class {
+                kind: "class",
+                name,
+            },
+            (2, _) => Definition::TypeAlias { name },
+            // Extension functions extend their receiver type
+            (3, _) => Definition::Extension { name },
+            _ => bail!("Unexpected pattern index"),
+        };
+
+        Ok(definition)
+    }
+
+    fn type_name(&self, node: Node, source: &str) -> String {
+        type_name(node, source)
+    }
+
+    fn scope(&self, declaration: Node, source: &str) -> Option<String> {
+        let mut names = vec![];
+        let mut current = declaration.parent();
+
+        while let Some(parent) = current {
+            if matches!(parent.kind(), "class_declaration" | "object_declaration") {
+                if let Some(name) = child(parent, "type_identifier") {
+                    names.push(&source[name.byte_range()]);
+                }
+            }
+
+            current = parent.parent();
+        }
+
+        if names.is_empty() {
+            return None;
+        }
+
+        names.reverse();
+
+        Some(names.join("."))
+    }
+
+    // Types nested in other types are qualified with the types before them, e.g.
+    // Map.Entry
+    fn reference_name(&self, node: Node, source: &str) -> String {
+        let Some(user_type) = node.parent().filter(|parent| parent.kind() == "user_type") else {
+            return source[node.byte_range()].to_string();
+        };
+
+        let mut cursor = user_type.walk();
+        let names: Vec<_> = user_type
+            .named_children(&mut cursor)
+            .take_while(|name| name.start_byte() <= node.start_byte())
+            .filter(|name| name.kind() == "type_identifier")
+            .map(|name| &source[name.byte_range()])
+            .collect();
+
+        names.join(".")
+    }
+
+    fn reference_kind(&self, node: Node, declaration: Node) -> &'static str {
+        let mut child = node;
+
+        while let Some(parent) = child.parent() {
+            match parent.kind() {
+                "function_body"
+                | "statements"
+                | "lambda_literal"
+                | "anonymous_initializer"
+                | "getter"
+                | "setter" => return "body",
+                "type_parameters" | "type_constraints" => return "generic",
+                // Superclasses are called, e.g. `: Base()`, interfaces aren't
+                "delegation_specifier" => {
+                    return if child.kind() == "constructor_invocation" {
+                        "inheritance"
+                    } else {
+                        "conformance"
+                    };
+                }
+                "class_parameter" => {
+                    let mut cursor = parent.walk();
+                    let is_property = parent
+                        .children(&mut cursor)
+                        .any(|token| matches!(token.kind(), "val" | "var"));
+
+                    return if is_property { "property" } else { "signature" };
+                }
+                "function_declaration" | "secondary_constructor" => return "signature",
+                "property_declaration" => {
+                    // The type of the property, rather than its initial value
+                    return if child.kind() == "variable_declaration" {
+                        "property"
+                    } else {
+                        "body"
+                    };
+                }
+                "type_alias" if parent.id() == declaration.id() => {
+                    // The aliased type itself, rather than its type arguments
+                    let is_aliased = child.kind() == "user_type"
+                        && node.parent() == Some(child)
+                        && node
+                            .next_named_sibling()
+                            .is_none_or(|next| next.kind() == "type_arguments");
+
+                    if is_aliased {
+                        return "alias";
+                    }
+                }
+                _ => (),
+            }
+
+            // Extension functions are declarations themselves
+            if parent.id() == declaration.id() {
+                break;
+            }
+
+            child = parent;
+        }
+
+        "body"
+    }
+
+    // Type identifiers certainly name types, other names only when called, e.g.
+    // `Formatter()`, or when members are accessed on them, e.g. `Formatter.Default`
+    fn reference_confidence(&self, node: Node) -> Option<&'static str> {
+        if node.kind() == "type_identifier" {
+            return Some("high");
+        }
+
+        let parent = node.parent()?;
+        let is_reference = matches!(parent.kind(), "call_expression" | "navigation_expression")
+            && node.prev_sibling().is_none();
+
+        is_reference.then_some("medium")
+    }
+}
+
+// Name of a type without type arguments, qualified for nested types, e.g. Outer.Inner
+// for Outer<T>.Inner
+fn type_name(node: Node, source: &str) -> String {
+    if node.kind() != "user_type" {
+        return source[node.byte_range()].to_string();
+    }
+
+    let mut cursor = node.walk();
+    let names: Vec<_> = node
+        .named_children(&mut cursor)
+        .filter(|name| name.kind() == "type_identifier")
+        .map(|name| &source[name.byte_range()])
+        .collect();
+
+    names.join(".")
+}
+
+// First named child of a kind
+fn child<'tree>(node: Node<'tree>, kind: &str) -> Option<Node<'tree>> {
+    let mut cursor = node.walk();
+    let child = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == kind);
+
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::parser::{CustomQueries, Parser};
+
+    // Declarations in the source with their references, e.g. `class Home: property Map`
+    fn declarations(source: &str) -> Vec<String> {
+        let parser = Parser::new(&Kotlin, &CustomQueries::default()).unwrap();
+        let tree = parser.parse(source.to_string()).unwrap();
+
+        tree.declarations()
+            .unwrap()
+            .into_iter()
+            .map(|declaration| {
+                let (kind, name) = crate::definition_parts(declaration.definition);
+                let references: Vec<_> = declaration
+                    .references
+                    .iter()
+                    .map(|reference| format!("{} {}", reference.kind, reference.name))
+                    .collect();
+
+                if references.is_empty() {
+                    format!("{} {}", kind, name)
+                } else {
+                    format!("{} {}: {}", kind, name, references.join(", "))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn qualifies_references_to_nested_types() {
+        let source = "class Cache {\n    val entries: List<Map.Entry<String, Int>> = listOf()\n}\n";
+
+        assert_eq!(
+            declarations(source),
+            vec!["class Cache: property List, property Map, property Map.Entry, property String, property Int"]
+        );
+    }
+
+    // Only the aliased type is an alias reference, not the types qualifying it or its
+    // type arguments
+    #[test]
+    fn declares_typealiases() {
+        let source = "typealias Entry = Map.Entry<String, Home>\n\nclass Home\n\ntypealias Screens = List<Home>\n";
+
+        assert_eq!(
+            declarations(source),
+            vec![
+                "typealias Entry: body Map, alias Map.Entry, body String, body Home",
+                "class Home",
+                "typealias Screens: alias List, body Home",
+            ]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+(class_declaration
+    ["class" "interface"] @kind
+    (type_identifier) @name
+) @declaration
+
+(object_declaration
+    (type_identifier) @name
+) @declaration
+
+(type_alias
+    (type_identifier) @name
+) @declaration
+
+(source_file
+    (function_declaration
+        (user_type) @name
+        "."
+        (simple_identifier)
+    ) @declaration
+)
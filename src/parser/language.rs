@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use tree_sitter::Node;
+
+use super::{Definition, Manifest, Tree};
+
+/// A language drake can index: its tree-sitter grammar, the files written in it, the
+/// queries finding declarations, references and imports, and the rules turning the
+/// matched syntax nodes into type names and reference kinds.
+pub trait Language: Send + Sync {
+    /// Lowercase name, e.g. "swift"
+    fn name(&self) -> &'static str;
+
+    fn grammar(&self) -> tree_sitter::Language;
+
+    /// Globs matching the names of source files, e.g. "*.swift"
+    fn file_globs(&self) -> &'static [&'static str];
+
+    /// Globs matching the names of package manifests, e.g. "Package.swift"
+    fn manifest_globs(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Query matching declarations as @declaration, with their @name and an optional @kind.
+    /// Declarations capturing an @aliased type name only alias that type, e.g. the
+    /// Objective-C name of a Swift class.
+    fn declarations_query(&self) -> &'static str;
+
+    /// Query matching names which may refer to types as @name
+    fn references_query(&self) -> &'static str;
+
+    /// Query matching the names of imported modules as @module
+    fn imports_query(&self) -> &'static str;
+
+    /// Name of the module imported by an @module node in the file at the path, None if
+    /// it doesn't name one
+    fn import_name(&self, node: Node, source: &str, _path: &Path) -> Option<String> {
+        Some(source[node.byte_range()].to_string())
+    }
+
+    /// Module of a source file at the path, for languages where each file is a module,
+    /// e.g. TypeScript. Other files belong to the module of their package target.
+    fn module(&self, _path: &Path) -> Option<String> {
+        None
+    }
+
+    /// Query matching the package name, products, dependencies and targets in a manifest
+    fn package_query(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Package described by a manifest at the path, by default found with the package
+    /// query. None for manifests describing no package, e.g. a Cargo workspace root.
+    fn manifest(&self, tree: &Tree, _path: &Path) -> anyhow::Result<Option<Manifest>> {
+        tree.package_manifest().map(Some)
+    }
+
+    /// Definition made by a match of a declarations query pattern, given the kind
+    /// of the @kind node, if the pattern captures one
+    fn definition(
+        &self,
+        pattern_index: usize,
+        declaration: Node,
+        kind: Option<&'static str>,
+        name: String,
+    ) -> anyhow::Result<Definition>;
+
+    /// Name of a declared type from its @name node, e.g. without generic arguments
+    fn type_name(&self, node: Node, source: &str) -> String;
+
+    /// Qualified name of the types a declaration is nested in, e.g. "Outer.Inner"
+    fn scope(&self, declaration: Node, source: &str) -> Option<String>;
+
+    /// Name of a referenced type, qualified with the types it was written nested in
+    fn reference_name(&self, node: Node, source: &str) -> String;
+
+    /// Kind of a reference made in a declaration: inheritance, conformance, property,
+    /// signature, generic, alias or body
+    fn reference_kind(&self, node: Node, declaration: Node) -> &'static str;
+
+    /// Confidence that a matched name refers to a type, "high" or "medium", None if it
+    /// doesn't
+    fn reference_confidence(&self, node: Node) -> Option<&'static str>;
+}
@@ -1,49 +1,122 @@
+mod cargo;
+mod gradle;
+mod kotlin;
+mod language;
+mod objc;
+mod queries;
+mod rust;
+mod swift;
 mod tree;
+mod tsconfig;
+mod typescript;
 
-use anyhow::anyhow;
-use tree_sitter::{Language, Query};
+use std::path::Path;
 
-pub use tree::{Declaration, Definition, Reference, Tree};
+use anyhow::{anyhow, Context};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tree_sitter::Query;
 
-// Matches a package name in a Package.swift file
-const PACKAGE_NAME_QUERY: &str = include_str!("package_name.scm");
-const DECLARATIONS_QUERY: &str = include_str!("declarations.scm");
-const REFERENCES_QUERY: &str = include_str!("references.scm");
+pub use kotlin::Kotlin;
+pub use language::Language;
+pub use objc::ObjC;
+pub use queries::{CustomQueries, QueryFile};
+pub use rust::Rust;
+pub use swift::Swift;
+pub use tree::{Declaration, Definition, Manifest, SyntaxError, Tree};
+pub use typescript::TypeScript;
+
+/// Languages indexed when scanning
+pub const LANGUAGES: &[&dyn Language] = &[
+    &Swift,
+    &Kotlin,
+    &ObjC,
+    &TypeScript { tsx: false },
+    &TypeScript { tsx: true },
+    &Rust,
+];
 
 pub struct Parser {
-    language: Language,
+    language: &'static dyn Language,
+    // Source files and manifests the parser handles
+    files: GlobSet,
     queries: Queries,
+    queries_hash: u64,
 }
 
 struct Queries {
-    package_name: Query,
+    package: Option<Query>,
     declaration: Query,
     reference: Query,
+    import: Query,
+    // Kinds set by the declaration patterns of a query file, by pattern index
+    declaration_kinds: Vec<Option<&'static str>>,
+    // Settings of the reference patterns of a query file, by pattern index
+    reference_settings: Vec<Option<queries::ReferenceSettings>>,
 }
 
 impl Parser {
-    pub fn new() -> Self {
-        let language = tree_sitter_swift::language();
+    /// Parser of the language, with the patterns of the custom query files added to its
+    /// queries or replacing them
+    pub fn new(language: &'static dyn Language, custom: &CustomQueries) -> anyhow::Result<Self> {
+        let grammar = language.grammar();
+        let name = language.name();
+
+        let (declaration, declaration_kinds) =
+            queries::declarations(language, custom.declarations.as_ref())
+                .with_context(|| format!("Failed to parse {name} declarations query"))?;
+        let (reference, reference_settings) =
+            queries::references(language, custom.references.as_ref())
+                .with_context(|| format!("Failed to parse {name} references query"))?;
 
         let queries = Queries {
-            package_name: Query::new(language, PACKAGE_NAME_QUERY)
-                .expect("Failed to parse package name query"),
-            declaration: Query::new(language, DECLARATIONS_QUERY)
-                .expect("Failed to parse declarations query"),
-            reference: Query::new(language, REFERENCES_QUERY)
-                .expect("Failed to parse references query"),
+            package: language.package_query().map(|query| {
+                Query::new(grammar, query)
+                    .unwrap_or_else(|e| panic!("Failed to parse {name} package query: {e}"))
+            }),
+            declaration,
+            reference,
+            import: Query::new(grammar, language.imports_query())
+                .unwrap_or_else(|e| panic!("Failed to parse {name} imports query: {e}")),
+            declaration_kinds,
+            reference_settings,
         };
 
-        Self { language, queries }
+        let mut files = GlobSetBuilder::new();
+
+        for glob in language
+            .file_globs()
+            .iter()
+            .chain(language.manifest_globs())
+        {
+            files.add(Glob::new(glob).expect("Invalid file glob"));
+        }
+
+        Ok(Self {
+            language,
+            files: files.build().expect("Invalid file globs"),
+            queries,
+            queries_hash: queries::hash(language, custom),
+        })
+    }
+
+    /// Hash of the parser's queries, including the custom query files
+    pub fn queries_hash(&self) -> u64 {
+        self.queries_hash
+    }
+
+    /// Whether the file is a source file or a manifest of the parser's language
+    pub fn handles(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|file_name| self.files.is_match(file_name))
     }
 
     pub fn parse(&self, source: String) -> anyhow::Result<Tree<'_>> {
         let mut parser = tree_sitter::Parser::new();
-        parser.set_language(self.language)?;
+        parser.set_language(self.language.grammar())?;
 
         let tree = parser
             .parse(&source, None)
-            .ok_or_else(|| anyhow!("Could not parse Swift source"))?;
+            .ok_or_else(|| anyhow!("Could not parse {} source", self.language.name()))?;
 
         Ok(Tree {
             source,
@@ -52,3 +125,252 @@ impl Parser {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parser(language: &'static dyn Language) -> Parser {
+        Parser::new(language, &CustomQueries::default()).unwrap()
+    }
+
+    // Built-in queries are only compiled at runtime, a query the grammar rejects fails
+    // every command
+    #[test]
+    fn builds_a_parser_for_every_language() {
+        for &language in LANGUAGES {
+            let parser = parser(language);
+            let tree = parser.parse(String::new()).unwrap();
+
+            assert!(
+                tree.declarations().unwrap().is_empty(),
+                "{}",
+                language.name()
+            );
+        }
+    }
+
+    #[test]
+    fn declares_swift_globals() {
+        let parser = parser(&Swift);
+        let tree = parser
+            .parse(
+                "let apiKey = \"\"\nvar retries = 3\n\nfunc start() {\n    let local = 1\n}\n"
+                    .to_string(),
+            )
+            .unwrap();
+
+        let globals: Vec<_> = tree
+            .declarations()
+            .unwrap()
+            .into_iter()
+            .filter_map(|declaration| match declaration.definition {
+                Definition::Variable { kind, name } => Some((kind, name)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            globals,
+            vec![
+                ("let", "apiKey".to_string()),
+                ("var", "retries".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn declares_swift_macros() {
+        let parser = parser(&Swift);
+        let tree = parser
+            .parse(
+                "@freestanding(expression)\nmacro stringify<T>(_ value: T) -> (T, String) = #externalMacro(module: \"Macros\", type: \"StringifyMacro\")\n"
+                    .to_string(),
+            )
+            .unwrap();
+
+        let macros: Vec<_> = tree
+            .declarations()
+            .unwrap()
+            .into_iter()
+            .filter_map(|declaration| match declaration.definition {
+                Definition::Macro { name } => Some(name),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(macros, vec!["stringify".to_string()]);
+    }
+
+    // Each file goes to the parser of the language its name matches
+    #[test]
+    fn dispatches_files_by_language() {
+        let parsers: Vec<_> = LANGUAGES.iter().map(|&language| parser(language)).collect();
+        let files = [
+            ("App/Router.swift", "swift"),
+            ("Package.swift", "swift"),
+            ("app/src/main/Router.kt", "kotlin"),
+            ("settings.gradle.kts", "kotlin"),
+            ("Legacy/RPRouter.h", "objc"),
+            ("Legacy/RPRouter.m", "objc"),
+            ("web/src/router.ts", "typescript"),
+            ("web/src/types.d.ts", "typescript"),
+            ("web/src/App.tsx", "tsx"),
+            ("core/src/lib.rs", "rust"),
+            ("core/Cargo.toml", "rust"),
+        ];
+
+        for (path, name) in files {
+            let languages: Vec<_> = parsers
+                .iter()
+                .filter(|parser| parser.handles(Path::new(path)))
+                .map(|parser| parser.language.name())
+                .collect();
+
+            assert_eq!(languages, vec![name], "{}", path);
+        }
+
+        assert!(!parsers
+            .iter()
+            .any(|parser| parser.handles(Path::new("README.md"))));
+    }
+
+    // A protocol and a type conforming to it in each language, with the references
+    // each declaration makes. Swift can't tell a superclass from a protocol, the index
+    // corrects the guess.
+    #[test]
+    fn declares_types_in_every_language() {
+        let interface = "interface Navigating {}\nclass Router implements Navigating {}\n";
+        let sources: [(&'static dyn Language, &str, &[&str]); 6] = [
+            (
+                &Swift,
+                "protocol Navigating {}\nclass Router: Navigating {}\n",
+                &[
+                    "protocol Navigating",
+                    "class Router: inheritance Navigating",
+                ],
+            ),
+            (
+                &Kotlin,
+                "interface Navigating\nclass Router : Navigating\n",
+                &[
+                    "protocol Navigating",
+                    "class Router: conformance Navigating",
+                ],
+            ),
+            (
+                &ObjC,
+                "@protocol Navigating\n@end\n\n@interface Router : NSObject <Navigating>\n@end\n",
+                &[
+                    "protocol Navigating",
+                    "class Router: inheritance NSObject, conformance Navigating",
+                ],
+            ),
+            (
+                &TypeScript { tsx: false },
+                interface,
+                &[
+                    "protocol Navigating",
+                    "class Router: conformance Navigating",
+                ],
+            ),
+            (
+                &TypeScript { tsx: true },
+                interface,
+                &[
+                    "protocol Navigating",
+                    "class Router: conformance Navigating",
+                ],
+            ),
+            (
+                &Rust,
+                "trait Navigating {}\nstruct Router;\nimpl Navigating for Router {}\n",
+                &[
+                    "protocol Navigating",
+                    "struct Router",
+                    "extension Router: conformance Navigating",
+                ],
+            ),
+        ];
+
+        for (language, source, expected) in sources {
+            let parser = parser(language);
+            let tree = parser.parse(source.to_string()).unwrap();
+
+            let declarations: Vec<_> = tree
+                .declarations()
+                .unwrap()
+                .into_iter()
+                .map(|declaration| {
+                    let (kind, name) = crate::definition_parts(declaration.definition);
+                    let references: Vec<_> = declaration
+                        .references
+                        .iter()
+                        .map(|reference| format!("{} {}", reference.kind, reference.name))
+                        .collect();
+
+                    if references.is_empty() {
+                        format!("{} {}", kind, name)
+                    } else {
+                        format!("{} {}: {}", kind, name, references.join(", "))
+                    }
+                })
+                .collect();
+
+            assert_eq!(declarations, expected, "{}", language.name());
+        }
+    }
+
+    // References in a nested type belong to it alone, and its name isn't a reference
+    // of the enclosing type
+    #[test]
+    fn credits_references_to_the_innermost_declaration() {
+        let sources: [(&'static dyn Language, &str, &[&str]); 2] = [
+            (
+                &Swift,
+                "class AppRouter {\n    struct Config {\n        let limit: Int\n    }\n\n    let config: Config\n}\n",
+                &[
+                    "AppRouter: Config at 5:16",
+                    "AppRouter.Config: Int at 2:19",
+                ],
+            ),
+            (
+                &Kotlin,
+                "class AppRouter {\n    class Config(val limit: Int)\n\n    val config: Config? = null\n}\n",
+                &[
+                    "AppRouter: Config at 3:16",
+                    "AppRouter.Config: Int at 1:28",
+                ],
+            ),
+        ];
+
+        for (language, source, expected) in sources {
+            let parser = parser(language);
+            let tree = parser.parse(source.to_string()).unwrap();
+
+            let declarations: Vec<_> = tree
+                .declarations()
+                .unwrap()
+                .into_iter()
+                .map(|declaration| {
+                    let (_, name) = crate::definition_parts(declaration.definition);
+                    let references: Vec<_> = declaration
+                        .references
+                        .iter()
+                        .map(|reference| {
+                            let location = reference.location;
+
+                            format!("{} at {}:{}", reference.name, location.row, location.column)
+                        })
+                        .collect();
+
+                    format!("{}: {}", name, references.join(", "))
+                })
+                .collect();
+
+            assert_eq!(declarations, expected, "{}", language.name());
+        }
+    }
+}
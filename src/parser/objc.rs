@@ -0,0 +1,270 @@
+use std::path::Path;
+
+use anyhow::bail;
+use tree_sitter::Node;
+
+use super::{Definition, Language};
+
+/// Objective-C headers and implementation files
+pub struct ObjC;
+
+impl Language for ObjC {
+    fn name(&self) -> &'static str {
+        "objc"
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        tree_sitter_objc::language()
+    }
+
+    fn file_globs(&self) -> &'static [&'static str] {
+        &["*.h", "*.m"]
+    }
+
+    fn declarations_query(&self) -> &'static str {
+        include_str!("objc/declarations.scm")
+    }
+
+    fn references_query(&self) -> &'static str {
+        include_str!("objc/references.scm")
+    }
+
+    fn imports_query(&self) -> &'static str {
+        include_str!("objc/imports.scm")
+    }
+
+    // Framework headers, e.g. <CoreKit/CKWidget.h>, belong to the CoreKit module.
+    // Headers of the same module are included with quotes and import nothing.
+    fn import_name(&self, node: Node, source: &str, _path: &Path) -> Option<String> {
+        let text = &source[node.byte_range()];
+
+        if node.kind() != "system_lib_string" {
+            return Some(text.to_string());
+        }
+
+        let (module, _) = text.trim_start_matches('<').split_once('/')?;
+
+        Some(module.to_string())
+    }
+
+    fn definition(
+        &self,
+        pattern_index: usize,
+        declaration: Node,
+        _kind: Option<&'static str>,
+        name: String,
+    ) -> anyhow::Result<Definition> {
+        let definition = match pattern_index {
+            // Categories and class extensions, e.g. `@interface UIView (Layout)`
+            0 if is_category(declaration) => Definition::Extension { name },
+            0 => Definition::Class {
+                kind: "class",
+                name,
+            },
+            // Implementations add to the class declared by the @interface
+            1 => Definition::Extension { name },
+            2 => Definition::Protocol { name },
+            _ => bail!("Unexpected pattern index"),
+        };
+
+        Ok(definition)
+    }
+
+    fn type_name(&self, node: Node, source: &str) -> String {
+        source[node.byte_range()].to_string()
+    }
+
+    // Objective-C types don't nest
+    fn scope(&self, _declaration: Node, _source: &str) -> Option<String> {
+        None
+    }
+
+    fn reference_name(&self, node: Node, source: &str) -> String {
+        source[node.byte_range()].to_string()
+    }
+
+    fn reference_kind(&self, node: Node, declaration: Node) -> &'static str {
+        let mut child = node;
+
+        while let Some(parent) = child.parent() {
+            match parent.kind() {
+                "compound_statement" => return "body",
+                // Protocols adopted by a class, e.g. `@interface Router : NSObject <Routing>`,
+                // or by a protocol, rather than protocol qualified types like `id<Routing>`
+                "parameterized_arguments" | "protocol_reference_list"
+                    if parent.parent().is_some_and(|owner| {
+                        matches!(owner.kind(), "class_interface" | "protocol_declaration")
+                    }) =>
+                {
+                    return "conformance"
+                }
+                "class_interface" | "class_implementation" if child == node => {
+                    return "inheritance"
+                }
+                "property_declaration" | "instance_variables" => return "property",
+                "method_declaration" | "method_definition" => return "signature",
+                _ => (),
+            }
+
+            if parent.id() == declaration.id() {
+                break;
+            }
+
+            child = parent;
+        }
+
+        "body"
+    }
+
+    // Type identifiers in declarations certainly name types, e.g. `NSString *`, as do
+    // superclasses and adopted protocols. Receivers of messages, e.g. `[Router shared]`,
+    // only when they are declared types. Other identifiers, like the declared name or
+    // the name of a category, aren't references.
+    fn reference_confidence(&self, node: Node) -> Option<&'static str> {
+        if node.kind() == "type_identifier" {
+            return Some("high");
+        }
+
+        let parent = node.parent()?;
+
+        match parent.kind() {
+            "class_interface" | "class_implementation" => node
+                .prev_sibling()
+                .is_some_and(|previous| previous.kind() == ":")
+                .then_some("high"),
+            "protocol_reference_list" => Some("high"),
+            "message_expression" => {
+                (parent.child_by_field_name("receiver") == Some(node)).then_some("medium")
+            }
+            _ => None,
+        }
+    }
+}
+
+// Categories name the extension in parentheses after the class, class extensions
+// leave the parentheses empty
+fn is_category(declaration: Node) -> bool {
+    let mut cursor = declaration.walk();
+    let is_category = declaration
+        .children(&mut cursor)
+        .any(|child| child.kind() == "(");
+
+    is_category
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::parser::{CustomQueries, Parser};
+
+    // Declarations in the source with their references, e.g.
+    // `class Router: inheritance NSObject high`
+    fn declarations(source: &str) -> Vec<String> {
+        let parser = Parser::new(&ObjC, &CustomQueries::default()).unwrap();
+        let tree = parser.parse(source.to_string()).unwrap();
+
+        tree.declarations()
+            .unwrap()
+            .into_iter()
+            .map(|declaration| {
+                let (kind, name) = crate::definition_parts(declaration.definition);
+                let references: Vec<_> = declaration
+                    .references
+                    .iter()
+                    .map(|reference| {
+                        format!(
+                            "{} {} {}",
+                            reference.kind, reference.name, reference.confidence
+                        )
+                    })
+                    .collect();
+
+                if references.is_empty() {
+                    format!("{} {}", kind, name)
+                } else {
+                    format!("{} {}: {}", kind, name, references.join(", "))
+                }
+            })
+            .collect()
+    }
+
+    fn imports(source: &str) -> Vec<String> {
+        let parser = Parser::new(&ObjC, &CustomQueries::default()).unwrap();
+        let tree = parser.parse(source.to_string()).unwrap();
+
+        tree.imports(Path::new("./App/App-Bridging-Header.h"))
+            .unwrap()
+    }
+
+    #[test]
+    fn declares_categories_as_extensions() {
+        let source = r#"@interface Router (Deeplinks)
+- (void)open:(Link *)link;
+@end
+
+@interface Router ()
+@property (nonatomic) Session *session;
+@end
+"#;
+
+        assert_eq!(
+            declarations(source),
+            vec![
+                "extension Router: signature Link high",
+                "extension Router: property Session high",
+            ]
+        );
+    }
+
+    #[test]
+    fn declares_implementations_as_extensions() {
+        let source = r#"@implementation Router
+- (void)start {
+    [Analytics track];
+    [self.session resume];
+}
+@end
+"#;
+
+        assert_eq!(
+            declarations(source),
+            vec!["extension Router: body Analytics medium"]
+        );
+    }
+
+    // Protocols in angle brackets after a superclass are adopted, while `id<Tracking>`
+    // only refers to a protocol
+    #[test]
+    fn tells_adopted_protocols_from_protocol_qualified_types() {
+        let source = r#"@protocol Routing <NSObject>
+@end
+
+@interface Router : NSObject <Routing, Tracking>
+@property (nonatomic) id<Tracking> tracker;
+@end
+"#;
+
+        assert_eq!(
+            declarations(source),
+            vec![
+                "protocol Routing: conformance NSObject high",
+                "class Router: inheritance NSObject high, conformance Routing high, conformance Tracking high, property Tracking high",
+            ]
+        );
+    }
+
+    // A bridging header's framework imports are visible to the Swift files next to it,
+    // headers of the app itself import no module
+    #[test]
+    fn imports_framework_modules_of_a_bridging_header() {
+        let source = r#"#import <CoreKit/CKWidget.h>
+#import <CoreKit/CKButton.h>
+#import "Legacy.h"
+@import Analytics;
+"#;
+
+        assert_eq!(imports(source), vec!["CoreKit", "Analytics"]);
+    }
+}
@@ -0,0 +1,14 @@
+(class_interface
+    .
+    (identifier) @name
+) @declaration
+
+(class_implementation
+    .
+    (identifier) @name
+) @declaration
+
+(protocol_declaration
+    .
+    (identifier) @name
+) @declaration
@@ -0,0 +1,8 @@
+(module_import
+    .
+    (identifier) @module
+)
+
+(preproc_include
+    path: (system_lib_string) @module
+)
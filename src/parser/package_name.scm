@@ -1,8 +0,0 @@
-(call_expression
-    (simple_identifier) @call_ident (#eq? @call_ident "Package")
-    (call_suffix
-        (value_arguments
-            (value_argument
-                (simple_identifier) @name_arg (#eq? @name_arg "name")
-                (line_string_literal
-                    (line_str_text) @package_name)))))
@@ -0,0 +1,307 @@
+use anyhow::{anyhow, bail, Context};
+use tree_sitter::{CaptureQuantifier, Query};
+
+use super::{Definition, Language};
+
+// Kinds patterns of a declarations query file set, e.g. `(#set! kind "class")`
+const DECLARATION_KINDS: &[&str] = &[
+    "class",
+    "struct",
+    "enum",
+    "actor",
+    "protocol",
+    "extension",
+    "typealias",
+    "associatedtype",
+    "function",
+    "variable",
+    "constant",
+    "macro",
+];
+
+// Kinds and confidences patterns of a references query file may set
+const REFERENCE_KINDS: &[&str] = &[
+    "inheritance",
+    "conformance",
+    "property",
+    "signature",
+    "generic",
+    "alias",
+    "body",
+];
+const CONFIDENCES: &[&str] = &["high", "medium"];
+
+/// A query file adding patterns to a built-in query of a language, or replacing it
+#[derive(Debug)]
+pub struct QueryFile {
+    /// Path of the file, as shown in errors
+    pub path: String,
+    pub source: String,
+    pub replace: bool,
+}
+
+/// Query files configured for a language
+#[derive(Debug, Default)]
+pub struct CustomQueries {
+    pub declarations: Option<QueryFile>,
+    pub references: Option<QueryFile>,
+}
+
+/// Kind and confidence set by a pattern of a references query file, the language
+/// decides the ones it leaves out
+#[derive(Debug, Clone, Copy)]
+pub struct ReferenceSettings {
+    pub kind: Option<&'static str>,
+    pub confidence: Option<&'static str>,
+}
+
+/// Declarations query of the language with the patterns of the query file, and the
+/// kind each pattern sets, None for built-in patterns
+pub fn declarations(
+    language: &dyn Language,
+    file: Option<&QueryFile>,
+) -> anyhow::Result<(Query, Vec<Option<&'static str>>)> {
+    let (query, file_query) = compile(
+        language,
+        language.declarations_query(),
+        file,
+        &["declaration", "name", "kind"],
+        &["declaration", "name"],
+    )?;
+
+    let mut kinds = vec![None; query.pattern_count()];
+
+    if let Some((file, file_query)) = file.zip(file_query.as_ref()) {
+        let first = query.pattern_count() - file_query.pattern_count();
+
+        for pattern in 0..file_query.pattern_count() {
+            let Some(kind) = setting(file, file_query, pattern, "kind", DECLARATION_KINDS)? else {
+                bail!(
+                    "Pattern at {} sets no kind, e.g. (#set! kind \"class\")",
+                    location(file, file_query, pattern)
+                );
+            };
+
+            kinds[first + pattern] = Some(kind);
+        }
+    }
+
+    Ok((query, kinds))
+}
+
+/// References query of the language with the patterns of the query file, and the
+/// settings of each pattern, None for built-in patterns
+pub fn references(
+    language: &dyn Language,
+    file: Option<&QueryFile>,
+) -> anyhow::Result<(Query, Vec<Option<ReferenceSettings>>)> {
+    let (query, file_query) = compile(
+        language,
+        language.references_query(),
+        file,
+        &["name"],
+        &["name"],
+    )?;
+
+    let mut settings = vec![None; query.pattern_count()];
+
+    if let Some((file, file_query)) = file.zip(file_query.as_ref()) {
+        let first = query.pattern_count() - file_query.pattern_count();
+
+        for pattern in 0..file_query.pattern_count() {
+            settings[first + pattern] = Some(ReferenceSettings {
+                kind: setting(file, file_query, pattern, "kind", REFERENCE_KINDS)?,
+                confidence: setting(file, file_query, pattern, "confidence", CONFIDENCES)?,
+            });
+        }
+    }
+
+    Ok((query, settings))
+}
+
+/// Hash of the queries of the language with the query files, which changes with the
+/// built-in queries, the files' sources and whether they replace the built-in queries
+pub fn hash(language: &dyn Language, custom: &CustomQueries) -> u64 {
+    let mut sources = vec![
+        language.name(),
+        language.package_query().unwrap_or_default(),
+        language.declarations_query(),
+        language.references_query(),
+        language.imports_query(),
+    ];
+
+    for file in [&custom.declarations, &custom.references] {
+        match file {
+            Some(file) if file.replace => sources.extend(["replace", &file.source]),
+            Some(file) => sources.extend(["extend", &file.source]),
+            None => sources.push("none"),
+        }
+    }
+
+    crate::content_hash(&sources.join("\0"))
+}
+
+/// Definition declared by a pattern of a query file with the kind it sets
+pub fn definition(kind: &'static str, name: String) -> Definition {
+    match kind {
+        "protocol" => Definition::Protocol { name },
+        "extension" => Definition::Extension { name },
+        "typealias" => Definition::TypeAlias { name },
+        "associatedtype" => Definition::AssociatedType { name },
+        "function" => Definition::Function { name },
+        "variable" => Definition::Variable { kind: "var", name },
+        "constant" => Definition::Variable { kind: "let", name },
+        "macro" => Definition::Macro { name },
+        // Classes, structs, enums and actors
+        kind => Definition::Class { kind, name },
+    }
+}
+
+// The built-in query followed by the patterns of the query file, or only the file's
+// patterns when it replaces the built-in query. The file is compiled by itself too, so
+// that its errors and patterns point into the file.
+fn compile(
+    language: &dyn Language,
+    builtin: &str,
+    file: Option<&QueryFile>,
+    captures: &[&str],
+    required: &[&str],
+) -> anyhow::Result<(Query, Option<Query>)> {
+    let grammar = language.grammar();
+
+    let Some(file) = file else {
+        return Ok((Query::new(grammar, builtin)?, None));
+    };
+
+    let file_query = Query::new(grammar, &file.source)
+        .with_context(|| format!("Invalid query file {}", file.path))?;
+
+    validate(file, &file_query, captures, required)?;
+
+    let query = if file.replace {
+        Query::new(grammar, &file.source)?
+    } else {
+        Query::new(grammar, &format!("{}\n{}", builtin, file.source))?
+    };
+
+    Ok((query, Some(file_query)))
+}
+
+// Query files capture only the names drake reads, or names starting with _ for
+// predicates, and each pattern captures the required ones in every match
+fn validate(
+    file: &QueryFile,
+    query: &Query,
+    captures: &[&str],
+    required: &[&str],
+) -> anyhow::Result<()> {
+    for name in query.capture_names() {
+        if !name.starts_with('_') && !captures.contains(&name.as_str()) {
+            bail!(
+                "Query file {} captures @{}, expected only @{} or names starting with _",
+                file.path,
+                name,
+                captures.join(", @")
+            );
+        }
+    }
+
+    for pattern in 0..query.pattern_count() {
+        for &name in required {
+            let is_captured = query.capture_index_for_name(name).is_some_and(|index| {
+                matches!(
+                    query.capture_quantifiers(pattern)[index as usize],
+                    CaptureQuantifier::One | CaptureQuantifier::OneOrMore
+                )
+            });
+
+            if !is_captured {
+                bail!(
+                    "Pattern at {} doesn't capture @{}",
+                    location(file, query, pattern),
+                    name
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Value a pattern sets for the key with #set!, which must be one of the values
+fn setting(
+    file: &QueryFile,
+    query: &Query,
+    pattern: usize,
+    key: &str,
+    values: &[&'static str],
+) -> anyhow::Result<Option<&'static str>> {
+    let Some(property) = query
+        .property_settings(pattern)
+        .iter()
+        .find(|property| &*property.key == key)
+    else {
+        return Ok(None);
+    };
+
+    let value = property.value.as_deref().unwrap_or_default();
+
+    values
+        .iter()
+        .find(|&&known| known == value)
+        .map(|&known| Some(known))
+        .ok_or_else(|| {
+            anyhow!(
+                "Pattern at {} sets {} to \"{}\", expected one of {}",
+                location(file, query, pattern),
+                key,
+                value,
+                values.join(", ")
+            )
+        })
+}
+
+// Path and line of a pattern of a query file, e.g. queries/declarations.scm:12
+fn location(file: &QueryFile, query: &Query, pattern: usize) -> String {
+    let start = query.start_byte_for_pattern(pattern);
+    let line = file.source[..start].matches('\n').count() + 1;
+
+    format!("{}:{}", file.path, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Swift;
+
+    fn references_file(source: &str) -> QueryFile {
+        QueryFile {
+            path: "queries/references.scm".to_string(),
+            source: source.to_string(),
+            replace: false,
+        }
+    }
+
+    #[test]
+    fn requires_a_name_in_every_match() {
+        let file = references_file("(user_type (type_identifier) @name)\n");
+
+        assert!(references(&Swift, Some(&file)).is_ok());
+
+        // Matches of an alternation or an optional node may miss the name
+        for source in [
+            "(call_expression [(simple_identifier) @name (navigation_expression)])\n",
+            "(user_type (type_identifier)? @name)\n",
+            "(user_type (type_identifier) @_type)\n",
+        ] {
+            let error = references(&Swift, Some(&references_file(source))).unwrap_err();
+
+            assert_eq!(
+                error.to_string(),
+                "Pattern at queries/references.scm:1 doesn't capture @name",
+                "{}",
+                source
+            );
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use anyhow::bail;
+use tree_sitter::Node;
+
+use super::{cargo, Definition, Language, Manifest, Tree};
+
+/// Rust sources and Cargo manifests
+pub struct Rust;
+
+impl Language for Rust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        tree_sitter_rust::language()
+    }
+
+    fn file_globs(&self) -> &'static [&'static str] {
+        &["*.rs"]
+    }
+
+    fn manifest_globs(&self) -> &'static [&'static str] {
+        &["Cargo.toml"]
+    }
+
+    fn declarations_query(&self) -> &'static str {
+        include_str!("rust/declarations.scm")
+    }
+
+    fn references_query(&self) -> &'static str {
+        include_str!("rust/references.scm")
+    }
+
+    fn imports_query(&self) -> &'static str {
+        include_str!("rust/imports.scm")
+    }
+
+    // Crates used by their first path segment, e.g. serde for `use serde::Deserialize`,
+    // rather than modules of the same crate, e.g. `use crate::index::Index`
+    fn import_name(&self, node: Node, source: &str, _path: &Path) -> Option<String> {
+        let path = source[node.byte_range()].trim_start_matches("::");
+        let first = path.split("::").next()?.split_whitespace().next()?;
+
+        let is_crate = first.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && !matches!(first, "crate" | "self" | "super");
+
+        is_crate.then(|| first.to_string())
+    }
+
+    fn manifest(&self, tree: &Tree, _path: &Path) -> anyhow::Result<Option<Manifest>> {
+        cargo::manifest(&tree.source)
+    }
+
+    fn definition(
+        &self,
+        pattern_index: usize,
+        _declaration: Node,
+        _kind: Option<&'static str>,
+        name: String,
+    ) -> anyhow::Result<Definition> {
+        let definition = match pattern_index {
+            0 => Definition::Class {
+                kind: "struct",
+                name,
+            },
+            1 => Definition::Class { kind: "enum", name },
+            2 => Definition::Protocol { name },
+            3 => Definition::TypeAlias { name },
+            // Both `impl Type` and `impl Trait for Type` extend the type
+            4 => Definition::Extension { name },
+            _ => bail!("Unexpected pattern index"),
+        };
+
+        Ok(definition)
+    }
+
+    fn type_name(&self, node: Node, source: &str) -> String {
+        type_name(node, source)
+    }
+
+    // Types nest in modules rather than in other types
+    fn scope(&self, _declaration: Node, _source: &str) -> Option<String> {
+        None
+    }
+
+    // Paths to types name modules rather than enclosing types, e.g. `index::Kind`,
+    // only the type's own name is kept
+    fn reference_name(&self, node: Node, source: &str) -> String {
+        source[node.byte_range()].to_string()
+    }
+
+    fn reference_kind(&self, node: Node, declaration: Node) -> &'static str {
+        let mut child = node;
+
+        while let Some(parent) = child.parent() {
+            match parent.kind() {
+                "block" => return "body",
+                "field_declaration" | "ordered_field_declaration_list" | "enum_variant" => {
+                    return "property"
+                }
+                // Supertraits, e.g. `trait Language: Send + Sync`
+                "trait_bounds"
+                    if parent
+                        .parent()
+                        .is_some_and(|owner| owner.kind() == "trait_item") =>
+                {
+                    return "conformance"
+                }
+                "type_parameters" | "where_clause" | "trait_bounds" | "associated_type" => {
+                    return "generic"
+                }
+                "parameters" | "function_item" | "function_signature_item" => return "signature",
+                "impl_item" if parent.child_by_field_name("trait") == Some(child) => {
+                    return "conformance"
+                }
+                "type_item" if parent.id() == declaration.id() => {
+                    // The aliased type itself, rather than its type arguments
+                    let is_aliased = parent.child_by_field_name("type") == Some(child)
+                        && (child == node
+                            || child.kind() == "generic_type"
+                                && child.child_by_field_name("type") == Some(node));
+
+                    if is_aliased {
+                        return "alias";
+                    }
+                }
+                _ => (),
+            }
+
+            if parent.id() == declaration.id() {
+                break;
+            }
+
+            child = parent;
+        }
+
+        "body"
+    }
+
+    // Type identifiers certainly name types, also in struct expressions, e.g.
+    // `Point { x, y }`. Other names only when their items are used, e.g. `Index::new()`,
+    // or when called, e.g. `Point(x, y)`. Enum variants and constants aren't references.
+    fn reference_confidence(&self, node: Node) -> Option<&'static str> {
+        if node.kind() == "type_identifier" {
+            return Some("high");
+        }
+
+        let parent = node.parent()?;
+        let is_reference = match parent.kind() {
+            "scoped_identifier" => parent.child_by_field_name("path") == Some(node),
+            "call_expression" => parent.child_by_field_name("function") == Some(node),
+            _ => false,
+        };
+
+        is_reference.then_some("medium")
+    }
+}
+
+// Name of a type without generic arguments or its path, e.g. Index for
+// `index::Index<'a>`, and the type behind references, e.g. Tree for `&mut Tree`
+fn type_name(node: Node, source: &str) -> String {
+    let inner = match node.kind() {
+        "generic_type" | "reference_type" | "pointer_type" => node.child_by_field_name("type"),
+        "scoped_type_identifier" => node.child_by_field_name("name"),
+        _ => None,
+    };
+
+    match inner {
+        Some(inner) => type_name(inner, source),
+        None => source[node.byte_range()].to_string(),
+    }
+}
@@ -0,0 +1,19 @@
+(struct_item
+    name: (type_identifier) @name
+) @declaration
+
+(enum_item
+    name: (type_identifier) @name
+) @declaration
+
+(trait_item
+    name: (type_identifier) @name
+) @declaration
+
+(type_item
+    name: (type_identifier) @name
+) @declaration
+
+(impl_item
+    type: (_) @name
+) @declaration
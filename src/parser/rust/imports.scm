@@ -0,0 +1,7 @@
+(use_declaration
+    argument: (_) @module
+)
+
+(extern_crate_declaration
+    name: (identifier) @module
+)
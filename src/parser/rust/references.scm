@@ -0,0 +1,3 @@
+((type_identifier) @name (#match? @name "^[A-Z]")) @name
+
+((identifier) @name (#match? @name "^[A-Z]")) @name
@@ -0,0 +1,234 @@
+use anyhow::bail;
+use tree_sitter::Node;
+
+use super::{Definition, Language};
+
+/// Swift sources and Swift package manager manifests
+pub struct Swift;
+
+impl Language for Swift {
+    fn name(&self) -> &'static str {
+        "swift"
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        tree_sitter_swift::language()
+    }
+
+    fn file_globs(&self) -> &'static [&'static str] {
+        &["*.swift"]
+    }
+
+    fn manifest_globs(&self) -> &'static [&'static str] {
+        &["Package.swift"]
+    }
+
+    fn declarations_query(&self) -> &'static str {
+        include_str!("swift/declarations.scm")
+    }
+
+    fn references_query(&self) -> &'static str {
+        include_str!("swift/references.scm")
+    }
+
+    fn imports_query(&self) -> &'static str {
+        include_str!("swift/imports.scm")
+    }
+
+    fn package_query(&self) -> Option<&'static str> {
+        Some(include_str!("swift/package_name.scm"))
+    }
+
+    fn definition(
+        &self,
+        pattern_index: usize,
+        _declaration: Node,
+        kind: Option<&'static str>,
+        name: String,
+    ) -> anyhow::Result<Definition> {
+        let definition = match (pattern_index, kind) {
+            (0, Some(kind)) => Definition::Class { kind, name },
+            (1, _) => Definition::Protocol { name },
+            (2, _) => Definition::Extension { name },
+            (3, _) => Definition::TypeAlias { name },
+            (4, _) => Definition::AssociatedType { name },
+            (5, _) => Definition::Function { name },
+            (6, Some(kind)) => Definition::Variable { kind, name },
+            (7, _) => Definition::Macro { name },
+            // Objective-C names given with @objc(Name)
+            (8 | 9, _) => Definition::TypeAlias { name },
+            _ => bail!("Unexpected pattern index"),
+        };
+
+        Ok(definition)
+    }
+
+    fn type_name(&self, node: Node, source: &str) -> String {
+        type_name(node, source)
+    }
+
+    fn scope(&self, declaration: Node, source: &str) -> Option<String> {
+        let mut names = vec![];
+        let mut current = declaration.parent();
+
+        while let Some(parent) = current {
+            if matches!(parent.kind(), "class_declaration" | "protocol_declaration") {
+                if let Some(name) = parent.child_by_field_name("name") {
+                    names.push(type_name(name, source));
+                }
+            }
+
+            current = parent.parent();
+        }
+
+        if names.is_empty() {
+            return None;
+        }
+
+        names.reverse();
+
+        Some(names.join("."))
+    }
+
+    fn reference_name(&self, node: Node, source: &str) -> String {
+        reference_name(node, source)
+    }
+
+    fn reference_kind(&self, node: Node, declaration: Node) -> &'static str {
+        reference_kind(node, declaration)
+    }
+
+    fn reference_confidence(&self, node: Node) -> Option<&'static str> {
+        reference_confidence(node)
+    }
+}
+
+// Name of a type without generic arguments, qualified for nested types, e.g. Outer.Inner
+// for Outer<T>.Inner
+fn type_name(node: Node, source: &str) -> String {
+    if node.kind() != "user_type" {
+        return source[node.byte_range()].to_string();
+    }
+
+    let mut cursor = node.walk();
+    let names: Vec<_> = node
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "type_identifier")
+        .map(|child| &source[child.byte_range()])
+        .collect();
+
+    names.join(".")
+}
+
+// Name of a referenced type, qualified with the types it was written nested in,
+// e.g. Outer.Inner for the second identifier in `Outer.Inner`
+fn reference_name(node: Node, source: &str) -> String {
+    let mut names = vec![&source[node.byte_range()]];
+    let mut sibling = node.prev_named_sibling();
+
+    if node
+        .parent()
+        .is_some_and(|parent| parent.kind() == "user_type")
+    {
+        while let Some(previous) = sibling {
+            if previous.kind() == "type_identifier" {
+                names.push(&source[previous.byte_range()]);
+            }
+
+            sibling = previous.prev_named_sibling();
+        }
+    }
+
+    names.reverse();
+    names.join(".")
+}
+
+// Classifies a reference by the syntax around it, looking up to the declaration.
+// Whether a supertype is a class or a protocol can't be told from the syntax, the first
+// supertype of a class is assumed to be its superclass.
+fn reference_kind(node: Node, declaration: Node) -> &'static str {
+    let mut child = node;
+
+    while let Some(parent) = child.parent() {
+        match parent.kind() {
+            "function_body" | "computed_property" | "statements" | "lambda_literal" => {
+                return "body"
+            }
+            "type_parameters" | "type_constraints" => return "generic",
+            "inheritance_specifier" => {
+                let is_class = parent
+                    .parent()
+                    .and_then(|owner| owner.child_by_field_name("declaration_kind"))
+                    .is_some_and(|kind| kind.kind() == "class");
+                let is_first = parent
+                    .prev_named_sibling()
+                    .is_none_or(|sibling| sibling.kind() != "inheritance_specifier");
+
+                return if is_class && is_first {
+                    "inheritance"
+                } else {
+                    "conformance"
+                };
+            }
+            "function_declaration"
+            | "init_declaration"
+            | "subscript_declaration"
+            | "protocol_function_declaration" => return "signature",
+            "property_declaration" | "protocol_property_declaration" => {
+                // The type of the property, rather than its initial value
+                return if child.kind() == "type_annotation" {
+                    "property"
+                } else {
+                    "body"
+                };
+            }
+            // Associated values
+            "enum_entry" => return "property",
+            "typealias_declaration" if parent.id() == declaration.id() => {
+                // The aliased type itself, rather than its generic arguments or its
+                // enclosing types
+                let is_aliased = parent.child_by_field_name("value") == Some(child)
+                    && child.kind() == "user_type"
+                    && node.parent() == Some(child)
+                    && node
+                        .next_named_sibling()
+                        .is_none_or(|sibling| sibling.kind() == "type_arguments");
+
+                if is_aliased {
+                    return "alias";
+                }
+            }
+            "associatedtype_declaration" => return "generic",
+            _ => (),
+        }
+
+        // Functions and variables are declarations themselves
+        if parent.id() == declaration.id() {
+            break;
+        }
+
+        child = parent;
+    }
+
+    "body"
+}
+
+// How likely an identifier is to name a type. Type identifiers certainly do, names in
+// expressions only when called, e.g. `Formatter()`, or when members are accessed on them,
+// e.g. `Formatter.shared`, and even then they may be e.g. constants. Other names, like
+// members (`.Foo`, `bar.Foo`), argument labels or bound names, aren't references.
+// Comments and string literals have no identifiers, apart from interpolated expressions.
+fn reference_confidence(node: Node) -> Option<&'static str> {
+    if node.kind() == "type_identifier" {
+        return Some("high");
+    }
+
+    let parent = node.parent()?;
+    let is_reference = match parent.kind() {
+        "call_expression" => node.prev_sibling().is_none(),
+        "navigation_expression" => parent.child_by_field_name("target") == Some(node),
+        _ => false,
+    };
+
+    is_reference.then_some("medium")
+}
@@ -0,0 +1,62 @@
+(class_declaration
+    declaration_kind: _ @kind
+    name: (type_identifier) @name
+) @declaration
+
+(protocol_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(class_declaration
+    declaration_kind: _ @kind
+    name: (user_type) @name
+) @declaration
+
+(typealias_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(associatedtype_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(source_file
+    (function_declaration
+        name: _ @name
+    ) @declaration
+)
+
+(source_file
+    (property_declaration
+        (value_binding_pattern
+            mutability: _ @kind
+        )
+        name: (pattern (simple_identifier) @name)
+    ) @declaration
+)
+
+(macro_declaration
+    (simple_identifier) @name
+) @declaration
+
+(class_declaration
+    (modifiers
+        (attribute
+            (user_type (type_identifier) @_attribute)
+            (simple_identifier) @name
+        )
+    )
+    name: (type_identifier) @aliased
+    (#eq? @_attribute "objc")
+) @declaration
+
+(protocol_declaration
+    (modifiers
+        (attribute
+            (user_type (type_identifier) @_attribute)
+            (simple_identifier) @name
+        )
+    )
+    name: (type_identifier) @aliased
+    (#eq? @_attribute "objc")
+) @declaration
@@ -0,0 +1,3 @@
+(import_declaration
+    (identifier . (simple_identifier) @module)
+)
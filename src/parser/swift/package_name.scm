@@ -0,0 +1,4 @@
+; The Package(...) call, its arguments are read by walking the call. Matching them
+; in the query instead makes compiling it take tens of seconds.
+(call_expression
+    (simple_identifier) @call_ident (#eq? @call_ident "Package")) @package
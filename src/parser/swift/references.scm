@@ -0,0 +1,3 @@
+((type_identifier) @name (#match? @name "^[A-Z]")) @name
+
+((simple_identifier) @name (#match? @name "^[A-Z]")) @name
@@ -1,9 +1,14 @@
-use std::fmt::{Display, Write};
-
-use anyhow::{anyhow, bail};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+    ops::Range,
+    path::Path,
+};
+
+use anyhow::anyhow;
 use tree_sitter::{Node, Point, QueryCursor};
 
-use super::Parser;
+use super::{queries, Parser};
 
 pub struct Tree<'parser> {
     pub parser: &'parser Parser,
@@ -13,9 +18,14 @@ pub struct Tree<'parser> {
 
 #[derive(Debug)]
 pub enum Definition {
-    Class { kind: &'static str, name: String }, // Swift classes, enums and structs all capture as Class
+    Class { kind: &'static str, name: String }, // Classes, enums, structs and actors all capture as Class
     Protocol { name: String },
     Extension { name: String },
+    TypeAlias { name: String },
+    AssociatedType { name: String },
+    Function { name: String }, // Top-level functions only
+    Variable { kind: &'static str, name: String }, // Global let or var
+    Macro { name: String },
 }
 
 #[derive(Debug)]
@@ -29,25 +39,353 @@ pub struct Declaration {
 pub struct Reference {
     pub name: String,
     pub location: Point,
+    pub kind: &'static str, // inheritance, conformance, property, signature, generic, alias or body
+    pub confidence: &'static str, // high in type positions, medium in expressions
+}
+
+/// Part of a file tree-sitter could not parse, declarations in it are missed
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub start: Point,
+    pub end: Point,
+    pub missing: Option<String>, // Kind of a node assumed to be missing, e.g. "}"
+}
+
+/// Contents of a Package.swift manifest
+#[derive(Debug)]
+pub struct Manifest {
+    pub name: String,
+    pub products: Vec<Product>,
+    pub dependencies: Vec<PackageDependency>,
+    pub targets: Vec<Target>,
+}
+
+#[derive(Debug)]
+pub struct Product {
+    pub name: String,
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PackageDependency {
+    /// Explicit name, or the last component of the URL or path
+    pub name: String,
+    /// Path of a local package
+    pub path: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Target {
+    pub kind: String, // target, executableTarget, testTarget, macro or plugin
+    pub name: String,
+    pub path: Option<String>,
+    pub dependencies: Vec<TargetDependency>,
+}
+
+#[derive(Debug)]
+pub struct TargetDependency {
+    pub name: String,
+    /// Package of a product dependency
+    pub package: Option<String>,
 }
 
 impl Tree<'_> {
-    pub fn package_name(&self) -> anyhow::Result<&str> {
-        let query = &self.parser.queries.package_name;
+    /// Package described by the manifest at the path, if any
+    pub fn manifest(&self, path: &Path) -> anyhow::Result<Option<Manifest>> {
+        self.parser.language.manifest(self, path)
+    }
+
+    /// Module of the source file at the path, if the file is a module by itself
+    pub fn module(&self, path: &Path) -> Option<String> {
+        self.parser.language.module(path)
+    }
+
+    /// Package found in the manifest with the language's package query, which matches
+    /// the package declaration
+    pub fn package_manifest(&self) -> anyhow::Result<Manifest> {
+        let query =
+            self.parser.queries.package.as_ref().ok_or_else(|| {
+                anyhow!("{} has no package manifests", self.parser.language.name())
+            })?;
         let mut query_cursor = QueryCursor::new();
 
-        let first_match = query_cursor
+        let package_index = query
+            .capture_index_for_name("package")
+            .ok_or_else(|| anyhow!("Failed parsing captures"))?;
+
+        let arguments_node = query_cursor
             .matches(query, self.tree.root_node(), self.source.as_bytes())
-            .next()
+            .find_map(|a_match| {
+                let package_node = a_match.nodes_for_capture_index(package_index).next()?;
+
+                self.call_arguments(package_node)
+            })
+            .ok_or_else(|| anyhow!("No matches for Package declaration"))?;
+
+        let arguments = self.arguments(arguments_node);
+        // Member calls in an array argument, e.g. .library(name: "Core") in products
+        let member_calls = |label: &str| {
+            argument(&arguments, label)
+                .map(|node| self.member_calls(node))
+                .unwrap_or_default()
+        };
+
+        let name = self
+            .string_argument(&arguments, "name")
             .ok_or_else(|| anyhow!("No matches for Package declaration"))?;
 
-        for capture in first_match.captures {
-            if capture.index == 2 {
-                return Ok(&self.source[capture.node.byte_range()]);
+        let mut products = vec![];
+        let mut dependencies = vec![];
+        let mut targets = vec![];
+
+        for (_, arguments) in member_calls("products") {
+            let arguments = self.arguments(arguments);
+            let Some(name) = self.string_argument(&arguments, "name") else {
+                continue;
+            };
+
+            products.push(Product {
+                name,
+                targets: argument(&arguments, "targets")
+                    .map(|node| self.strings(node))
+                    .unwrap_or_default(),
+            });
+        }
+
+        for (member, arguments) in member_calls("dependencies") {
+            if member != "package" {
+                continue;
+            }
+
+            let arguments = self.arguments(arguments);
+            let path = self.string_argument(&arguments, "path");
+            let identity = self
+                .string_argument(&arguments, "url")
+                .or_else(|| path.clone())
+                .map(|location| package_identity(&location));
+
+            let Some(name) = self.string_argument(&arguments, "name").or(identity) else {
+                continue;
+            };
+
+            dependencies.push(PackageDependency { name, path });
+        }
+
+        for (member, arguments) in member_calls("targets") {
+            let arguments = self.arguments(arguments);
+            let Some(name) = self.string_argument(&arguments, "name") else {
+                continue;
+            };
+
+            targets.push(Target {
+                kind: member.to_string(),
+                name,
+                path: self.string_argument(&arguments, "path"),
+                dependencies: argument(&arguments, "dependencies")
+                    .map(|node| self.target_dependencies(node))
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(Manifest {
+            name,
+            products,
+            dependencies,
+            targets,
+        })
+    }
+
+    // Implicit member calls in an array literal, e.g. [.target(name: "Core")]
+    fn member_calls<'a>(&'a self, array: Node<'a>) -> Vec<(&'a str, Node<'a>)> {
+        let mut cursor = array.walk();
+
+        array
+            .named_children(&mut cursor)
+            .filter_map(|element| self.member_call(element))
+            .collect()
+    }
+
+    // Dependencies of a target, given as names, .target(name:), .byName(name:)
+    // or .product(name:package:)
+    fn target_dependencies(&self, array: Node) -> Vec<TargetDependency> {
+        let mut cursor = array.walk();
+        let mut dependencies = vec![];
+
+        for element in array.named_children(&mut cursor) {
+            if let Some(name) = self.string(element) {
+                dependencies.push(TargetDependency {
+                    name,
+                    package: None,
+                });
+
+                continue;
             }
+
+            let Some((member, arguments)) = self.member_call(element) else {
+                continue;
+            };
+
+            let arguments = self.arguments(arguments);
+
+            let Some(name) = self.string_argument(&arguments, "name") else {
+                continue;
+            };
+
+            let package = match member {
+                "product" => Some(
+                    self.string_argument(&arguments, "package")
+                        .unwrap_or_else(|| name.clone()),
+                ),
+                "target" | "byName" => None,
+                _ => continue,
+            };
+
+            dependencies.push(TargetDependency { name, package });
+        }
+
+        dependencies
+    }
+
+    // Name and arguments of an implicit member call, e.g. .product(name: "Foo")
+    fn member_call<'a>(&'a self, node: Node<'a>) -> Option<(&'a str, Node<'a>)> {
+        if node.kind() != "call_expression" {
+            return None;
         }
 
-        bail!("No matches for Package declaration")
+        let member = node.named_child(0)?;
+
+        if member.kind() != "prefix_expression" {
+            return None;
+        }
+
+        let name = member.named_child(member.named_child_count().checked_sub(1)?)?;
+
+        Some((&self.source[name.byte_range()], self.call_arguments(node)?))
+    }
+
+    // Arguments of a call expression in parentheses
+    fn call_arguments<'a>(&'a self, call: Node<'a>) -> Option<Node<'a>> {
+        let suffix = call.named_child(1)?;
+
+        if suffix.kind() != "call_suffix" {
+            return None;
+        }
+
+        let mut cursor = suffix.walk();
+        let arguments = suffix
+            .named_children(&mut cursor)
+            .find(|child| child.kind() == "value_arguments");
+
+        arguments
+    }
+
+    // Arguments of a call with their labels
+    fn arguments<'a>(&'a self, node: Node<'a>) -> Vec<(Option<&'a str>, Node<'a>)> {
+        let mut cursor = node.walk();
+
+        node.named_children(&mut cursor)
+            .filter(|argument| argument.kind() == "value_argument")
+            .filter_map(|argument| {
+                let mut cursor = argument.walk();
+                let children: Vec<_> = argument.children(&mut cursor).collect();
+
+                let value = *children.last()?;
+                let label = children
+                    .iter()
+                    .position(|child| child.kind() == ":")
+                    .and_then(|colon| colon.checked_sub(1))
+                    .map(|idx| &self.source[children[idx].byte_range()]);
+
+                Some((label, value))
+            })
+            .collect()
+    }
+
+    // Contents of the string literal passed as the argument with the label
+    fn string_argument(&self, arguments: &[(Option<&str>, Node)], label: &str) -> Option<String> {
+        argument(arguments, label).and_then(|node| self.string(node))
+    }
+
+    // Contents of a string literal, None for other nodes
+    fn string(&self, node: Node) -> Option<String> {
+        if node.kind() != "line_string_literal" {
+            return None;
+        }
+
+        let mut cursor = node.walk();
+        let text = node
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() == "line_str_text")
+            .map(|child| &self.source[child.byte_range()])
+            .collect();
+
+        Some(text)
+    }
+
+    // String literals in an array literal
+    fn strings(&self, array: Node) -> Vec<String> {
+        let mut cursor = array.walk();
+
+        array
+            .named_children(&mut cursor)
+            .filter_map(|element| self.string(element))
+            .collect()
+    }
+
+    /// Ranges of the file which failed to parse and nodes tree-sitter assumed missing,
+    /// in the order they appear
+    pub fn syntax_errors(&self) -> Vec<SyntaxError> {
+        let mut errors = vec![];
+        let mut nodes = vec![self.tree.root_node()];
+
+        while let Some(node) = nodes.pop() {
+            if node.is_error() || node.is_missing() {
+                errors.push(SyntaxError {
+                    start: node.start_position(),
+                    end: node.end_position(),
+                    missing: node.is_missing().then(|| node.kind().to_string()),
+                });
+            } else if node.has_error() {
+                let mut cursor = node.walk();
+                nodes.extend(node.children(&mut cursor));
+            }
+        }
+
+        errors.sort_by_key(|error| error.start);
+
+        errors
+    }
+
+    /// Names of the modules imported by the file at the path, e.g. Core for
+    /// `@testable import Core`
+    pub fn imports(&self, path: &Path) -> anyhow::Result<Vec<String>> {
+        let query = &self.parser.queries.import;
+        let mut query_cursor = QueryCursor::new();
+
+        let module_index = query
+            .capture_index_for_name("module")
+            .ok_or_else(|| anyhow!("Failed parsing captures"))?;
+
+        let mut imports = vec![];
+
+        let matches = query_cursor.matches(query, self.tree.root_node(), self.source.as_bytes());
+
+        for a_match in matches {
+            let module_node = a_match
+                .nodes_for_capture_index(module_index)
+                .next()
+                .unwrap();
+            let language = self.parser.language;
+            let Some(module) = language.import_name(module_node, &self.source, path) else {
+                continue;
+            };
+
+            if !imports.contains(&module) {
+                imports.push(module);
+            }
+        }
+
+        Ok(imports)
     }
 
     pub fn declarations(&self) -> anyhow::Result<Vec<Declaration>> {
@@ -56,60 +394,115 @@ impl Tree<'_> {
 
         let mut declarations = vec![];
 
-        let kind_index = query
-            .capture_index_for_name("kind")
-            .ok_or_else(|| anyhow!("Failed parsing captures"))?;
+        // Only some languages capture the declaration's keyword as its kind
+        let kind_index = query.capture_index_for_name("kind");
         let name_index = query
             .capture_index_for_name("name")
             .ok_or_else(|| anyhow!("Failed parsing captures"))?;
         let declaration_index = query
             .capture_index_for_name("declaration")
             .ok_or_else(|| anyhow!("Failed parsing captures"))?;
+        let aliased_index = query.capture_index_for_name("aliased");
 
         let matches = query_cursor.matches(query, self.tree.root_node(), self.source.as_bytes());
 
+        // Pattern, name and declaration nodes of each match, and its kind and aliased nodes
+        let mut declaration_matches = vec![];
+
         for a_match in matches {
-            let name_node = a_match.nodes_for_capture_index(name_index).next().unwrap();
-            let kind_node = a_match.nodes_for_capture_index(kind_index).next();
-            let match_node = a_match
-                .nodes_for_capture_index(declaration_index)
-                .next()
-                .unwrap();
+            // A match without a name or declaration declares nothing
+            let (Some(name_node), Some(match_node)) = (
+                a_match.nodes_for_capture_index(name_index).next(),
+                a_match.nodes_for_capture_index(declaration_index).next(),
+            ) else {
+                continue;
+            };
+            let kind_node =
+                kind_index.and_then(|index| a_match.nodes_for_capture_index(index).next());
+            let aliased_node =
+                aliased_index.and_then(|index| a_match.nodes_for_capture_index(index).next());
+
+            declaration_matches.push((
+                a_match.pattern_index,
+                name_node,
+                match_node,
+                kind_node,
+                aliased_node,
+            ));
+        }
 
-            let definition = match a_match.pattern_index {
-                0 => Definition::Class {
-                    kind: kind_node.unwrap().kind(),
-                    name: self.source[name_node.byte_range()].to_string(),
-                },
-                1 => Definition::Protocol {
-                    name: self.source[name_node.byte_range()].to_string(),
-                },
-                2 => Definition::Extension {
-                    name: self.source[name_node.byte_range()].to_string(),
-                },
-                _ => bail!("Unexpected pattern index"),
+        for &(pattern_index, name_node, match_node, kind_node, aliased_node) in &declaration_matches
+        {
+            // Names of all declarations, and declarations nested in this one, which are
+            // credited with the references they make
+            let excluded: Vec<_> = declaration_matches
+                .iter()
+                .map(|&(_, name, declaration, _, _)| {
+                    let is_nested = declaration.byte_range() != match_node.byte_range()
+                        && declaration.start_byte() >= match_node.start_byte()
+                        && declaration.end_byte() <= match_node.end_byte();
+
+                    if is_nested {
+                        declaration.byte_range()
+                    } else {
+                        name.byte_range()
+                    }
+                })
+                .collect();
+
+            let language = self.parser.language;
+            let type_name = language.type_name(name_node, &self.source);
+            let name = match language.scope(match_node, &self.source) {
+                Some(scope) => format!("{}.{}", scope, type_name),
+                None => type_name,
+            };
+
+            // Patterns of query files set the kind they declare
+            let definition = match self.parser.queries.declaration_kinds[pattern_index] {
+                Some(kind) => queries::definition(kind, name),
+                None => language.definition(
+                    pattern_index,
+                    match_node,
+                    kind_node.map(|node| node.kind()),
+                    name,
+                )?,
+            };
+
+            // Another name of the aliased type only refers to it
+            let references = match aliased_node {
+                Some(aliased) => vec![Reference {
+                    name: language.reference_name(aliased, &self.source),
+                    location: aliased.start_position(),
+                    kind: "alias",
+                    confidence: "high",
+                }],
+                None => self.references_in(match_node, &excluded, &self.source)?,
             };
 
             declarations.push(Declaration {
                 definition,
                 location: name_node.start_position(),
-                references: self.references_in(match_node, &self.source)?,
+                references,
             })
         }
 
         Ok(declarations)
     }
 
-    pub fn references(&self, source: &str) -> anyhow::Result<Vec<Reference>> {
-        self.references_in(self.tree.root_node(), source)
-    }
-
-    fn references_in(&self, node: Node, source: &str) -> anyhow::Result<Vec<Reference>> {
+    // References in a declaration, except for names in the excluded byte ranges
+    fn references_in(
+        &self,
+        node: Node,
+        excluded: &[Range<usize>],
+        source: &str,
+    ) -> anyhow::Result<Vec<Reference>> {
         let query = &self.parser.queries.reference;
 
         let mut query_cursor = QueryCursor::new();
 
         let mut references = vec![];
+        // Index of the reference made by each name node
+        let mut recorded = HashMap::new();
 
         let name_index = query
             .capture_index_for_name("name")
@@ -118,22 +511,71 @@ impl Tree<'_> {
         let matches = query_cursor.matches(query, node, source.as_bytes());
 
         for a_match in matches {
-            let name_node = a_match.nodes_for_capture_index(name_index).next().unwrap();
+            let Some(name_node) = a_match.nodes_for_capture_index(name_index).next() else {
+                continue;
+            };
+
+            let is_excluded = excluded.iter().any(|range| {
+                name_node.start_byte() >= range.start && name_node.end_byte() <= range.end
+            });
+
+            if is_excluded {
+                continue;
+            }
+
+            let language = self.parser.language;
+            let settings = self.parser.queries.reference_settings[a_match.pattern_index];
+
+            // Names matched by patterns of query files are references even where the
+            // language wouldn't take them for one
+            let confidence = match settings {
+                Some(settings) => settings
+                    .confidence
+                    .or_else(|| language.reference_confidence(name_node))
+                    .unwrap_or("medium"),
+                None => match language.reference_confidence(name_node) {
+                    Some(confidence) if !recorded.contains_key(&name_node.id()) => confidence,
+                    _ => continue,
+                },
+            };
 
-            references.push(Reference {
-                name: source[name_node.byte_range()].to_string(),
+            let reference = Reference {
+                name: language.reference_name(name_node, source),
                 location: name_node.start_position(),
-            })
+                kind: settings
+                    .and_then(|settings| settings.kind)
+                    .unwrap_or_else(|| language.reference_kind(name_node, node)),
+                confidence,
+            };
+
+            // Patterns of query files override built-in patterns matching the same name
+            match recorded.get(&name_node.id()) {
+                Some(&idx) => references[idx] = reference,
+                None => {
+                    recorded.insert(name_node.id(), references.len());
+                    references.push(reference);
+                }
+            }
         }
 
         Ok(references)
     }
 }
 
-impl Default for Parser {
-    fn default() -> Self {
-        Self::new()
-    }
+// Value of the argument with the label
+fn argument<'a>(arguments: &[(Option<&str>, Node<'a>)], label: &str) -> Option<Node<'a>> {
+    arguments
+        .iter()
+        .find(|(argument_label, _)| *argument_label == Some(label))
+        .map(|(_, node)| *node)
+}
+
+// Swift package manager identity of a package URL or path, its last path component
+fn package_identity(location: &str) -> String {
+    let location = location.trim_end_matches('/');
+    let name = location.rsplit('/').next().unwrap_or(location);
+
+    name.trim_end_matches(".git").to_string()
 }
 
 impl Display for Tree<'_> {
@@ -204,3 +646,156 @@ impl Display for Tree<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::parser::{CustomQueries, Swift};
+
+    const PACKAGE: &str = r#"// swift-tools-version:5.9
+import PackageDescription
+
+let package = Package(
+    name: "App",
+    platforms: [.iOS(.v16)],
+    products: [
+        .library(name: "Core", targets: ["Core", "Networking"]),
+        .executable(name: "app", targets: ["App"]),
+    ],
+    dependencies: [
+        .package(url: "https://github.com/apple/swift-log.git", from: "1.0.0"),
+        .package(path: "../DesignSystem"),
+        .package(name: "Utils", path: "Vendor/utils"),
+    ],
+    targets: [
+        .target(
+            name: "Core",
+            dependencies: [
+                "Networking",
+                .product(name: "Logging", package: "swift-log"),
+                .product(name: "DesignSystem"),
+            ]
+        ),
+        .target(name: "Networking", path: "Sources/Net"),
+        .testTarget(name: "CoreTests", dependencies: [.target(name: "Core")]),
+    ]
+)
+"#;
+
+    fn manifest(source: &str) -> anyhow::Result<Manifest> {
+        let parser = Parser::new(&Swift, &CustomQueries::default())?;
+
+        parser.parse(source.to_string())?.package_manifest()
+    }
+
+    #[test]
+    fn reads_products_dependencies_and_targets() {
+        let manifest = manifest(PACKAGE).unwrap();
+
+        assert_eq!(manifest.name, "App");
+        assert_eq!(
+            manifest
+                .products
+                .iter()
+                .map(|product| (product.name.as_str(), product.targets.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Core", vec!["Core".to_string(), "Networking".to_string()]),
+                ("app", vec!["App".to_string()]),
+            ]
+        );
+        assert_eq!(
+            manifest
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.name.as_str(), dependency.path.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("swift-log", None),
+                ("DesignSystem", Some("../DesignSystem")),
+                ("Utils", Some("Vendor/utils")),
+            ]
+        );
+        assert_eq!(
+            manifest
+                .targets
+                .iter()
+                .map(|target| (
+                    target.kind.as_str(),
+                    target.name.as_str(),
+                    target.path.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("target", "Core", None),
+                ("target", "Networking", Some("Sources/Net")),
+                ("testTarget", "CoreTests", None),
+            ]
+        );
+        assert_eq!(
+            manifest.targets[0]
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.name.as_str(), dependency.package.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Networking", None),
+                ("Logging", Some("swift-log")),
+                ("DesignSystem", Some("DesignSystem")),
+            ]
+        );
+        assert_eq!(manifest.targets[2].dependencies[0].name, "Core".to_string());
+    }
+
+    #[test]
+    fn fails_without_a_package_declaration() {
+        assert!(manifest("let answer = 42\n").is_err());
+    }
+
+    #[test]
+    fn identifies_packages_by_their_last_path_component() {
+        assert_eq!(
+            package_identity("https://github.com/apple/swift-log.git"),
+            "swift-log"
+        );
+        assert_eq!(
+            package_identity("../Packages/DesignSystem/"),
+            "DesignSystem"
+        );
+        assert_eq!(package_identity("Vendor"), "Vendor");
+    }
+
+    #[test]
+    fn skips_the_declared_name_in_references() {
+        let parser = Parser::new(&Swift, &CustomQueries::default()).unwrap();
+        let tree = parser
+            .parse(
+                "class Router: Navigating {\n    var next: Router?\n}\n\nextension Router: Routing {}\n"
+                    .to_string(),
+            )
+            .unwrap();
+
+        let references: Vec<_> = tree
+            .declarations()
+            .unwrap()
+            .iter()
+            .map(|declaration| {
+                declaration
+                    .references
+                    .iter()
+                    .map(|reference| (reference.name.clone(), reference.location.row))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert_eq!(
+            references,
+            vec![
+                vec![("Navigating".to_string(), 0), ("Router".to_string(), 1)],
+                vec![("Routing".to_string(), 4)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use serde_json::Value;
+
+// Extensions of TypeScript sources, declaration files first as they end in .ts too
+const EXTENSIONS: &[&str] = &[".d.ts", ".tsx", ".ts"];
+
+// Suffixes tried to find the source file of an imported path, e.g. ./router/index.ts
+// for ./router
+const SOURCE_SUFFIXES: &[&str] = &[
+    "",
+    ".ts",
+    ".tsx",
+    ".d.ts",
+    "/index.ts",
+    "/index.tsx",
+    "/index.d.ts",
+];
+
+/// Module of a TypeScript source file, its path without the extension, e.g.
+/// ./web/src/router for ./web/src/router.ts
+pub fn module(file: &Path) -> String {
+    let path = file.to_string_lossy();
+
+    EXTENSIONS
+        .iter()
+        .find_map(|extension| path.strip_suffix(extension))
+        .unwrap_or(&path)
+        .to_string()
+}
+
+/// Module a file imports with a module specifier: a relative path, e.g. ./router, or
+/// a path mapped by `paths` or found under `baseUrl` in the closest tsconfig.json.
+/// None for packages, e.g. react, and for paths which aren't scanned sources.
+pub fn resolve_import(file: &Path, specifier: &str) -> Option<String> {
+    let candidates = if specifier.starts_with('.') {
+        vec![file.parent()?.join(specifier)]
+    } else {
+        let (directory, config) = config(file)?;
+
+        mapped_paths(&directory, &config, specifier)
+    };
+
+    candidates
+        .iter()
+        .find_map(|candidate| source_file(&normalize(candidate)))
+        .map(|source| module(&source))
+}
+
+// Closest tsconfig.json above the file, with the directory it is in
+fn config(file: &Path) -> Option<(PathBuf, Value)> {
+    let directory = file
+        .ancestors()
+        .skip(1)
+        .filter(|directory| !directory.as_os_str().is_empty())
+        .find(|directory| directory.join("tsconfig.json").is_file())?;
+
+    let source = fs::read_to_string(directory.join("tsconfig.json")).ok()?;
+    let config = serde_json::from_str(&to_json(&source)).ok()?;
+
+    Some((directory.to_path_buf(), config))
+}
+
+// Paths a specifier may refer to: the targets of the `paths` pattern with the longest
+// prefix matching it, then the specifier under `baseUrl`. Both are relative to
+// `baseUrl`, or to the directory of the tsconfig.json without one.
+fn mapped_paths(directory: &Path, config: &Value, specifier: &str) -> Vec<PathBuf> {
+    let options = &config["compilerOptions"];
+    let base = match options["baseUrl"].as_str() {
+        Some(base_url) => directory.join(base_url),
+        None => directory.to_path_buf(),
+    };
+
+    let mut candidates = vec![];
+
+    let best_match = options["paths"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(pattern, targets)| {
+            let prefix = pattern.find('*').unwrap_or(pattern.len());
+
+            Some((prefix, match_pattern(pattern, specifier)?, targets))
+        })
+        .max_by_key(|&(prefix, _, _)| prefix);
+
+    if let Some((_, matched, targets)) = best_match {
+        candidates.extend(
+            targets
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|target| base.join(target.replace('*', matched))),
+        );
+    }
+
+    if options["baseUrl"].is_string() {
+        candidates.push(base.join(specifier));
+    }
+
+    candidates
+}
+
+// Part of the specifier matched by the wildcard of a `paths` pattern, e.g. ui/Button
+// for @app/ui/Button and @app/*, empty for patterns without one
+fn match_pattern<'a>(pattern: &str, specifier: &'a str) -> Option<&'a str> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => specifier
+            .strip_prefix(prefix)?
+            .strip_suffix(suffix)
+            .filter(|matched| !matched.is_empty()),
+        None => (pattern == specifier).then_some(""),
+    }
+}
+
+// Source file an imported path refers to. ES modules import sources by the name of
+// the compiled file, e.g. ./router.js for ./router.ts
+fn source_file(path: &Path) -> Option<PathBuf> {
+    let path = path.to_string_lossy();
+    let path = path.strip_suffix(".js").unwrap_or(&path);
+
+    SOURCE_SUFFIXES
+        .iter()
+        .map(|suffix| PathBuf::from(format!("{}{}", path, suffix)))
+        .find(|candidate| candidate.is_file())
+}
+
+// Path without `.` and `..` components, keeping a leading `./` like the scanned paths
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+// tsconfig.json allows comments and trailing commas, which JSON doesn't
+fn to_json(source: &str) -> String {
+    let mut json = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => (),
+            }
+
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                json.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.next_if_eq(&'/').is_some() {
+                        break;
+                    }
+                }
+            }
+            ('}' | ']', _) => {
+                let end = json.trim_end().len();
+
+                if json[..end].ends_with(',') {
+                    json.truncate(end - 1);
+                }
+
+                json.push(c);
+            }
+            _ => json.push(c),
+        }
+    }
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_tsconfig_with_comments() {
+        let source = r#"{
+            // Paths are relative to src
+            "compilerOptions": {
+                "baseUrl": "./src", /* see below */
+                "paths": { "@app/*": ["app/*",], },
+                "outDir": "dist//http://",
+            },
+        }"#;
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&to_json(source)).unwrap(),
+            json!({
+                "compilerOptions": {
+                    "baseUrl": "./src",
+                    "paths": { "@app/*": ["app/*"] },
+                    "outDir": "dist//http://"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn maps_specifiers_with_the_longest_matching_pattern() {
+        let config = json!({
+            "compilerOptions": {
+                "baseUrl": "src",
+                "paths": {
+                    "@app/*": ["app/*"],
+                    "@app/ui/*": ["ui/*", "vendor/ui/*"],
+                    "config": ["config/index"]
+                }
+            }
+        });
+        let directory = Path::new("./web");
+
+        assert_eq!(
+            mapped_paths(directory, &config, "@app/ui/Button"),
+            vec![
+                PathBuf::from("./web/src/ui/Button"),
+                PathBuf::from("./web/src/vendor/ui/Button"),
+                PathBuf::from("./web/src/@app/ui/Button"),
+            ]
+        );
+        assert_eq!(
+            mapped_paths(directory, &config, "config"),
+            vec![
+                PathBuf::from("./web/src/config/index"),
+                PathBuf::from("./web/src/config"),
+            ]
+        );
+        assert_eq!(
+            normalize(Path::new("./web/src/app/../../lib/./router")),
+            PathBuf::from("./web/lib/router")
+        );
+        assert_eq!(module(Path::new("./web/src/types.d.ts")), "./web/src/types");
+    }
+}
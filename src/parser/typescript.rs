@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use anyhow::bail;
+use tree_sitter::Node;
+
+use super::{tsconfig, Definition, Language};
+
+/// TypeScript sources, TSX sources with `tsx` set. Every file is a module of its own.
+pub struct TypeScript {
+    pub tsx: bool,
+}
+
+impl Language for TypeScript {
+    fn name(&self) -> &'static str {
+        if self.tsx {
+            "tsx"
+        } else {
+            "typescript"
+        }
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        if self.tsx {
+            tree_sitter_typescript::language_tsx()
+        } else {
+            tree_sitter_typescript::language_typescript()
+        }
+    }
+
+    fn file_globs(&self) -> &'static [&'static str] {
+        if self.tsx {
+            &["*.tsx"]
+        } else {
+            &["*.ts"]
+        }
+    }
+
+    fn declarations_query(&self) -> &'static str {
+        include_str!("typescript/declarations.scm")
+    }
+
+    fn references_query(&self) -> &'static str {
+        include_str!("typescript/references.scm")
+    }
+
+    fn imports_query(&self) -> &'static str {
+        include_str!("typescript/imports.scm")
+    }
+
+    fn import_name(&self, node: Node, source: &str, path: &Path) -> Option<String> {
+        let specifier = source[node.byte_range()].trim_matches(['"', '\'']);
+
+        tsconfig::resolve_import(path, specifier)
+    }
+
+    fn module(&self, path: &Path) -> Option<String> {
+        Some(tsconfig::module(path))
+    }
+
+    fn definition(
+        &self,
+        pattern_index: usize,
+        _declaration: Node,
+        _kind: Option<&'static str>,
+        name: String,
+    ) -> anyhow::Result<Definition> {
+        let definition = match pattern_index {
+            0 | 1 => Definition::Class {
+                kind: "class",
+                name,
+            },
+            // Interfaces are implemented like protocols are adopted
+            2 => Definition::Protocol { name },
+            3 => Definition::TypeAlias { name },
+            4 => Definition::Class { kind: "enum", name },
+            5 => Definition::Function { name },
+            _ => bail!("Unexpected pattern index"),
+        };
+
+        Ok(definition)
+    }
+
+    fn type_name(&self, node: Node, source: &str) -> String {
+        source[node.byte_range()].to_string()
+    }
+
+    // Types are declared at the top level of a module
+    fn scope(&self, _declaration: Node, _source: &str) -> Option<String> {
+        None
+    }
+
+    // Names imported under another name, e.g. `import { Router as AppRouter }`, refer
+    // to the name the imported module declares. Names qualified with a namespace import,
+    // e.g. `api.Client`, refer to a type of the imported module.
+    fn reference_name(&self, node: Node, source: &str) -> String {
+        let name = &source[node.byte_range()];
+        let mut root = node;
+
+        while let Some(parent) = root.parent() {
+            root = parent;
+        }
+
+        let mut cursor = root.walk();
+
+        let imported_name = root
+            .named_children(&mut cursor)
+            .filter(|statement| statement.kind() == "import_statement")
+            .flat_map(|statement| import_specifiers(statement))
+            .find_map(|specifier| {
+                let alias = specifier.child_by_field_name("alias")?;
+                let imported = specifier.child_by_field_name("name")?;
+
+                (&source[alias.byte_range()] == name).then(|| &source[imported.byte_range()])
+            });
+
+        imported_name.unwrap_or(name).to_string()
+    }
+
+    fn reference_kind(&self, node: Node, declaration: Node) -> &'static str {
+        let mut child = node;
+
+        while let Some(parent) = child.parent() {
+            match parent.kind() {
+                "statement_block" => return "body",
+                "extends_clause" => return "inheritance",
+                "implements_clause" | "extends_type_clause" => return "conformance",
+                "type_parameters" => return "generic",
+                "public_field_definition" | "property_signature" => {
+                    // The type of the field, rather than its initial value
+                    return if child.kind() == "type_annotation" {
+                        "property"
+                    } else {
+                        "body"
+                    };
+                }
+                "formal_parameters"
+                | "method_definition"
+                | "method_signature"
+                | "abstract_method_signature"
+                | "function_declaration" => return "signature",
+                "type_alias_declaration" if parent.id() == declaration.id() => {
+                    // The aliased type itself, rather than its type arguments
+                    let is_aliased = parent.child_by_field_name("value") == Some(child)
+                        && (child == node
+                            || child.kind() == "generic_type"
+                                && child.child_by_field_name("name") == Some(node));
+
+                    if is_aliased {
+                        return "alias";
+                    }
+                }
+                _ => (),
+            }
+
+            // Exported functions are declarations themselves
+            if parent.id() == declaration.id() {
+                break;
+            }
+
+            child = parent;
+        }
+
+        "body"
+    }
+
+    // Type identifiers certainly name types, as do superclasses, e.g. `extends Base`.
+    // Other names only when constructed, e.g. `new Formatter()`, or when members are
+    // accessed on them, e.g. `Formatter.shared`.
+    fn reference_confidence(&self, node: Node) -> Option<&'static str> {
+        if node.kind() == "type_identifier" {
+            return Some("high");
+        }
+
+        let parent = node.parent()?;
+
+        match parent.kind() {
+            "extends_clause" => Some("high"),
+            "new_expression" => {
+                (parent.child_by_field_name("constructor") == Some(node)).then_some("medium")
+            }
+            "member_expression" => {
+                (parent.child_by_field_name("object") == Some(node)).then_some("medium")
+            }
+            _ => None,
+        }
+    }
+}
+
+// Specifiers of an import statement's named imports, e.g. `Router as AppRouter`
+fn import_specifiers(statement: Node) -> Vec<Node> {
+    let mut specifiers = vec![];
+    let mut nodes = vec![statement];
+
+    while let Some(node) = nodes.pop() {
+        if node.kind() == "import_specifier" {
+            specifiers.push(node);
+        } else {
+            let mut cursor = node.walk();
+            nodes.extend(node.named_children(&mut cursor));
+        }
+    }
+
+    specifiers
+}
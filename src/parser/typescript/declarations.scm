@@ -0,0 +1,25 @@
+(class_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(abstract_class_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(interface_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(type_alias_declaration
+    name: (type_identifier) @name
+) @declaration
+
+(enum_declaration
+    name: (identifier) @name
+) @declaration
+
+(export_statement
+    declaration: (function_declaration
+        name: (identifier) @name
+    ) @declaration
+)
@@ -0,0 +1,7 @@
+(import_statement
+    source: (string) @module
+)
+
+(export_statement
+    source: (string) @module
+)
@@ -1,6 +1,6 @@
 use std::{path::Path, sync::Arc, thread};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use crossbeam::channel::{unbounded, Receiver};
 use ignore::{WalkParallel, WalkState};
 
@@ -18,7 +18,13 @@ impl<T> Iterator for Results<T> {
     }
 }
 
-pub fn process_files<F, Output>(walk: WalkParallel, process_file: F) -> Results<Result<Output>>
+/// Processes the walked files on all CPUs, each with the parser of the language which
+/// handles the file
+pub fn process_files<F, Output>(
+    walk: WalkParallel,
+    parsers: Arc<Vec<Parser>>,
+    process_file: F,
+) -> Results<Result<Output>>
 where
     F: Fn(&Path, &Parser) -> Result<Output> + Send + Sync + 'static,
     Output: Send + 'static,
@@ -55,12 +61,14 @@ where
             let result_tx = result_tx.clone();
             let task_rx = task_rx.clone();
             let work = work.clone();
+            let parsers = parsers.clone();
 
             move || {
-                let parser = Parser::new();
-
                 while let Ok(path) = task_rx.recv() {
-                    let result = work(&path, &parser);
+                    let result = match parsers.iter().find(|parser| parser.handles(&path)) {
+                        Some(parser) => work(&path, parser),
+                        None => Err(anyhow!("No language handles {}", path.display())),
+                    };
 
                     result_tx.send(result).expect("Can't send result");
                 }